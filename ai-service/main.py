@@ -1,7 +1,7 @@
 from fastapi import FastAPI, HTTPException
 from fastapi.middleware.cors import CORSMiddleware
 from pydantic import BaseModel
-from typing import Dict, Any
+from typing import Dict, Any, Optional
 import os
 from dotenv import load_dotenv
 import json
@@ -32,6 +32,12 @@ analyzer = ResumeAnalyzer(
 class CritiqueRequest(BaseModel):
     resume_text: str
     filename: str
+    job_description: Optional[str] = None
+    # Overrides MODEL_NAME; the backend only sends models listed in AI_MODELS
+    model: Optional[str] = None
+    # Reviewer preferences, passed to the model as delimited data in the
+    # human message; they cannot change the scoring criteria
+    rubric: Optional[str] = None
 
 class CritiqueResponse(BaseModel):
     overall_score: float
@@ -53,7 +59,10 @@ async def critique_resume(request: CritiqueRequest):
         # Analyze the resume using LangChain and OpenAI
         analysis_result = await analyzer.analyze_resume(
             resume_text=request.resume_text,
-            filename=request.filename
+            filename=request.filename,
+            job_description=request.job_description,
+            model=request.model,
+            rubric=request.rubric
         )
         
         return CritiqueResponse(**analysis_result)
@@ -0,0 +1,9 @@
+-- Roles for authorization checks; existing accounts become regular users
+ALTER TABLE users ADD COLUMN IF NOT EXISTS role VARCHAR(20) NOT NULL DEFAULT 'user';
+
+-- Non-guessable identifiers exposed through the API in place of SERIAL ids
+ALTER TABLE resumes ADD COLUMN IF NOT EXISTS public_id UUID NOT NULL DEFAULT gen_random_uuid();
+ALTER TABLE critiques ADD COLUMN IF NOT EXISTS public_id UUID NOT NULL DEFAULT gen_random_uuid();
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_resumes_public_id ON resumes(public_id);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_critiques_public_id ON critiques(public_id);
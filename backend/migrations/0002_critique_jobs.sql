@@ -0,0 +1,22 @@
+-- Queue of pending AI critiques, processed by the backend's worker pool
+CREATE TABLE IF NOT EXISTS critique_jobs (
+    id SERIAL PRIMARY KEY,
+    public_id UUID NOT NULL DEFAULT gen_random_uuid(),
+    resume_id INTEGER NOT NULL REFERENCES resumes(id) ON DELETE CASCADE,
+    status VARCHAR(20) NOT NULL DEFAULT 'queued'
+        CHECK (status IN ('queued', 'running', 'succeeded', 'failed')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    critique_id INTEGER REFERENCES critiques(id) ON DELETE SET NULL,
+    error TEXT,
+    -- Earliest time a queued job may be claimed (used for retry backoff)
+    available_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    -- A running job whose lease has expired belongs to a dead worker
+    locked_until TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    started_at TIMESTAMP WITH TIME ZONE,
+    finished_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_critique_jobs_public_id ON critique_jobs(public_id);
+CREATE INDEX IF NOT EXISTS idx_critique_jobs_claim ON critique_jobs(status, available_at);
+CREATE INDEX IF NOT EXISTS idx_critique_jobs_resume_id ON critique_jobs(resume_id);
@@ -0,0 +1,3 @@
+-- Latest progress stage of a critique job, replayed to clients that start
+-- watching after the job has begun. Jobs are created once text is extracted.
+ALTER TABLE critique_jobs ADD COLUMN IF NOT EXISTS stage VARCHAR(30) NOT NULL DEFAULT 'text_extracted';
@@ -0,0 +1,2 @@
+-- Machine-readable reason for the last failure, alongside the error message
+ALTER TABLE critique_jobs ADD COLUMN IF NOT EXISTS error_code VARCHAR(40);
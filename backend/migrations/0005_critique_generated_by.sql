@@ -0,0 +1,3 @@
+-- Which engine produced a critique: the AI service, or the backend's
+-- heuristic scorer when the AI service was unavailable
+ALTER TABLE critiques ADD COLUMN IF NOT EXISTS generated_by VARCHAR(20) NOT NULL DEFAULT 'ai';
@@ -0,0 +1,3 @@
+-- Corrections made to the AI service's output before it was stored (clamped
+-- scores, reshaped suggestions) and the reason a fallback scorer was used
+ALTER TABLE critiques ADD COLUMN IF NOT EXISTS validation_issues JSONB NOT NULL DEFAULT '[]';
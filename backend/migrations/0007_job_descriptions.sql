@@ -0,0 +1,22 @@
+-- Target postings a resume can be critiqued against
+CREATE TABLE IF NOT EXISTS job_descriptions (
+    id SERIAL PRIMARY KEY,
+    public_id UUID NOT NULL DEFAULT gen_random_uuid(),
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    title VARCHAR(255) NOT NULL,
+    company VARCHAR(255),
+    description TEXT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_job_descriptions_public_id ON job_descriptions(public_id);
+CREATE INDEX IF NOT EXISTS idx_job_descriptions_user_id ON job_descriptions(user_id);
+
+ALTER TABLE critique_jobs ADD COLUMN IF NOT EXISTS job_description_id INTEGER
+    REFERENCES job_descriptions(id) ON DELETE SET NULL;
+
+-- Keyword coverage against the posting; NULL for critiques without one
+ALTER TABLE critiques ADD COLUMN IF NOT EXISTS job_description_id INTEGER
+    REFERENCES job_descriptions(id) ON DELETE SET NULL;
+ALTER TABLE critiques ADD COLUMN IF NOT EXISTS fit_score REAL;
+ALTER TABLE critiques ADD COLUMN IF NOT EXISTS keyword_report JSONB;
@@ -0,0 +1,33 @@
+-- feedback_history records each resume as a numbered version within a
+-- lineage (the successive uploads of one resume). critique_id is filled in
+-- when the version's critique completes.
+ALTER TABLE feedback_history ADD COLUMN IF NOT EXISTS lineage_id UUID;
+ALTER TABLE feedback_history ALTER COLUMN critique_id DROP NOT NULL;
+ALTER TABLE feedback_history ALTER COLUMN created_at SET NOT NULL;
+
+-- Existing entries take their owner from their resume; ones that still have
+-- no owner or resume can't be shown to anyone and are dropped
+UPDATE feedback_history fh SET user_id = r.user_id
+FROM resumes r
+WHERE fh.user_id IS NULL AND r.id = fh.resume_id;
+DELETE FROM feedback_history WHERE user_id IS NULL OR resume_id IS NULL;
+
+-- Every existing resume becomes version 1 of its own lineage. Resumes with
+-- no owner belong to no one's history and are left out.
+INSERT INTO feedback_history (user_id, resume_id, critique_id, version_number)
+SELECT r.user_id, r.id,
+       (SELECT c.id FROM critiques c WHERE c.resume_id = r.id ORDER BY c.created_at DESC LIMIT 1),
+       1
+FROM resumes r
+WHERE r.user_id IS NOT NULL
+  AND NOT EXISTS (SELECT 1 FROM feedback_history fh WHERE fh.resume_id = r.id);
+
+UPDATE feedback_history SET lineage_id = gen_random_uuid() WHERE lineage_id IS NULL;
+
+ALTER TABLE feedback_history ALTER COLUMN lineage_id SET NOT NULL;
+ALTER TABLE feedback_history ALTER COLUMN user_id SET NOT NULL;
+ALTER TABLE feedback_history ALTER COLUMN resume_id SET NOT NULL;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_feedback_history_resume_id ON feedback_history(resume_id);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_feedback_history_lineage_version
+    ON feedback_history(lineage_id, version_number);
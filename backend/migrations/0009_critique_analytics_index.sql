@@ -0,0 +1,2 @@
+-- Score analytics walk a user's critiques in upload order
+CREATE INDEX IF NOT EXISTS idx_critiques_resume_id_created_at ON critiques(resume_id, created_at);
@@ -0,0 +1,6 @@
+-- Per-critique AI options, chosen when a critique is requested and recorded
+-- with the critique they produced (NULL: the service's defaults)
+ALTER TABLE critique_jobs ADD COLUMN IF NOT EXISTS model VARCHAR(100);
+ALTER TABLE critique_jobs ADD COLUMN IF NOT EXISTS rubric TEXT;
+ALTER TABLE critiques ADD COLUMN IF NOT EXISTS model VARCHAR(100);
+ALTER TABLE critiques ADD COLUMN IF NOT EXISTS rubric TEXT;
@@ -0,0 +1,11 @@
+-- Hashes used to recognise repeated uploads: file_hash of the exact bytes,
+-- text_hash of the whitespace-normalised extracted text. NULL for resumes
+-- uploaded before hashing, which are never matched.
+ALTER TABLE resumes ADD COLUMN IF NOT EXISTS file_hash CHAR(64);
+ALTER TABLE resumes ADD COLUMN IF NOT EXISTS text_hash CHAR(64);
+CREATE INDEX IF NOT EXISTS idx_resumes_user_file_hash ON resumes(user_id, file_hash);
+CREATE INDEX IF NOT EXISTS idx_resumes_user_text_hash ON resumes(user_id, text_hash);
+
+-- RUBRIC_VERSION in effect when the critique was scored; a cached critique
+-- is only reused under the same version
+ALTER TABLE critiques ADD COLUMN IF NOT EXISTS rubric_version VARCHAR(50);
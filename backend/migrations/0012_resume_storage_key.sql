@@ -0,0 +1,22 @@
+-- Files are addressed by a key relative to the storage backend (UPLOAD_DIR
+-- or an S3 bucket) instead of a local path. Existing paths become keys
+-- relative to UPLOAD_DIR: the sharded ab/cd/<id> layout is kept, older
+-- <uuid>-<name> files sit directly in it.
+ALTER TABLE resumes ADD COLUMN IF NOT EXISTS storage_key VARCHAR(500);
+
+DO $$
+BEGIN
+    IF EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_name = 'resumes' AND column_name = 'file_path'
+    ) THEN
+        UPDATE resumes
+        SET storage_key = COALESCE(
+            substring(file_path FROM '([0-9a-f]{2}/[0-9a-f]{2}/[0-9a-f]{32})$'),
+            regexp_replace(file_path, '^.*/', '')
+        )
+        WHERE storage_key IS NULL AND file_path IS NOT NULL;
+    END IF;
+END $$;
+
+ALTER TABLE resumes DROP COLUMN IF EXISTS file_path;
@@ -0,0 +1,6 @@
+-- Envelope encryption: each resume's file and original_content are sealed
+-- with its own data key, stored here wrapped by the master key named in
+-- data_key_id. NULL for resumes stored before encryption was enabled,
+-- whose file and text are plaintext.
+ALTER TABLE resumes ADD COLUMN IF NOT EXISTS data_key BYTEA;
+ALTER TABLE resumes ADD COLUMN IF NOT EXISTS data_key_id VARCHAR(64);
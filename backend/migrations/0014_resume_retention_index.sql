@@ -0,0 +1,2 @@
+-- The retention purge looks up resumes by upload time
+CREATE INDEX IF NOT EXISTS idx_resumes_uploaded_at ON resumes(uploaded_at);
@@ -0,0 +1,10 @@
+-- Scores are f32 throughout the backend; store them as REAL so queries can
+-- bind and read them without casts. Values are 0-5 with at most one
+-- decimal, which REAL holds exactly enough for display.
+ALTER TABLE critiques
+    ALTER COLUMN overall_score TYPE REAL,
+    ALTER COLUMN structure_score TYPE REAL,
+    ALTER COLUMN keywords_score TYPE REAL,
+    ALTER COLUMN action_verbs_score TYPE REAL,
+    ALTER COLUMN quantified_impact_score TYPE REAL,
+    ALTER COLUMN readability_score TYPE REAL;
@@ -0,0 +1,18 @@
+-- Every row gets these on insert, but the columns were created nullable,
+-- so reads had to treat them as optional. Backfill anything missing and
+-- make the schema say so.
+UPDATE users SET created_at = NOW() WHERE created_at IS NULL;
+UPDATE users SET updated_at = created_at WHERE updated_at IS NULL;
+ALTER TABLE users
+    ALTER COLUMN created_at SET NOT NULL,
+    ALTER COLUMN updated_at SET NOT NULL;
+
+UPDATE resumes SET uploaded_at = NOW() WHERE uploaded_at IS NULL;
+ALTER TABLE resumes ALTER COLUMN uploaded_at SET NOT NULL;
+
+-- A critique without a resume can't be reached through the API
+DELETE FROM critiques WHERE resume_id IS NULL;
+UPDATE critiques SET created_at = NOW() WHERE created_at IS NULL;
+ALTER TABLE critiques
+    ALTER COLUMN resume_id SET NOT NULL,
+    ALTER COLUMN created_at SET NOT NULL;
@@ -0,0 +1,301 @@
+use rand::Rng;
+use reqwest::StatusCode;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+use crate::models::AiCritiqueRequest;
+
+const RETRY_BASE: Duration = Duration::from_millis(250);
+const RETRY_MAX: Duration = Duration::from_secs(5);
+
+#[derive(Debug)]
+pub enum AiError {
+    // The breaker is open; no request was sent.
+    CircuitOpen { retry_in: Duration },
+    Timeout,
+    Unavailable(String),
+    Rejected(StatusCode),
+    InvalidResponse(String),
+}
+
+impl AiError {
+    // Stable identifier for clients to branch on; the message may change.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AiError::CircuitOpen { .. } => "ai_circuit_open",
+            AiError::Timeout => "ai_timeout",
+            AiError::Unavailable(_) => "ai_unavailable",
+            AiError::Rejected(_) => "ai_rejected",
+            AiError::InvalidResponse(_) => "ai_invalid_response",
+        }
+    }
+
+    // Failures that say the service is unhealthy rather than that this
+    // particular request was bad. Only these are retried and only these
+    // count towards opening the breaker.
+    fn is_transient(&self) -> bool {
+        matches!(self, AiError::Timeout | AiError::Unavailable(_))
+    }
+}
+
+impl fmt::Display for AiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AiError::CircuitOpen { retry_in } => write!(
+                f,
+                "AI service is unavailable; not retrying for another {}s",
+                retry_in.as_secs().max(1)
+            ),
+            AiError::Timeout => write!(f, "AI service timed out"),
+            AiError::Unavailable(e) => write!(f, "AI service error: {}", e),
+            AiError::Rejected(status) => write!(f, "AI service rejected the request with status {}", status),
+            AiError::InvalidResponse(e) => write!(f, "AI service returned an invalid response: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for AiError {}
+
+impl From<reqwest::Error> for AiError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            AiError::Timeout
+        } else if e.is_decode() {
+            AiError::InvalidResponse(e.to_string())
+        } else {
+            AiError::Unavailable(e.to_string())
+        }
+    }
+}
+
+// Client for the Python critique service. One instance is shared by every
+// worker so connections are pooled and the breaker sees all traffic.
+pub struct AiClient {
+    http: reqwest::Client,
+    base_url: String,
+    max_retries: u32,
+    breaker: CircuitBreaker,
+}
+
+impl AiClient {
+    pub fn new(config: &Config) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.ai_timeout_secs))
+            .connect_timeout(Duration::from_secs(5))
+            .pool_idle_timeout(Duration::from_secs(90))
+            .build()
+            .expect("Failed to build AI service HTTP client");
+
+        Self {
+            http,
+            base_url: config.ai_service_url.trim_end_matches('/').to_string(),
+            max_retries: config.ai_max_retries,
+            breaker: CircuitBreaker::new(
+                config.ai_breaker_threshold,
+                Duration::from_secs(config.ai_breaker_cooldown_secs),
+            ),
+        }
+    }
+
+    // Generating a critique has no side effects on the AI service, so a
+    // transient failure can safely be retried. The body is returned as raw
+    // JSON; callers validate its shape (see `validation`).
+    pub async fn critique(&self, request: &AiCritiqueRequest) -> Result<serde_json::Value, AiError> {
+        let mut attempt = 0;
+        loop {
+            let permit = self.breaker.check()?;
+
+            let result = self.send(request).await;
+            match &result {
+                Ok(_) => permit.success(),
+                Err(e) if e.is_transient() => permit.failure(),
+                // The service answered; it is healthy even if this request failed.
+                Err(_) => permit.success(),
+            }
+
+            match result {
+                Err(e) if e.is_transient() && attempt < self.max_retries => {
+                    eprintln!("AI service request failed (attempt {}): {}", attempt + 1, e);
+                    tokio::time::sleep(backoff(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn send(&self, request: &AiCritiqueRequest) -> Result<serde_json::Value, AiError> {
+        let response = self
+            .http
+            .post(format!("{}/critique", self.base_url))
+            .json(request)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS {
+            return Err(AiError::Unavailable(format!("status {}", status)));
+        }
+        if !status.is_success() {
+            return Err(AiError::Rejected(status));
+        }
+
+        Ok(response.json().await?)
+    }
+}
+
+// Exponential backoff with full jitter, so workers retrying after the same
+// outage don't hit the service in lockstep.
+fn backoff(attempt: u32) -> Duration {
+    let ceiling = RETRY_BASE.saturating_mul(1 << attempt.min(16)).min(RETRY_MAX);
+    rand::thread_rng().gen_range(Duration::ZERO..=ceiling)
+}
+
+struct BreakerState {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+    probing: bool,
+}
+
+// Opens after `threshold` consecutive transient failures and rejects calls
+// until `cooldown` has passed. Then a single probe request is let through:
+// success closes the breaker, failure opens it for another cooldown.
+struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    state: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            threshold: threshold.max(1),
+            cooldown,
+            state: Mutex::new(BreakerState {
+                consecutive_failures: 0,
+                open_until: None,
+                probing: false,
+            }),
+        }
+    }
+
+    fn check(&self) -> Result<Permit<'_>, AiError> {
+        let mut state = self.state.lock().unwrap();
+        let Some(open_until) = state.open_until else {
+            return Ok(Permit { breaker: self, probe: false });
+        };
+
+        let now = Instant::now();
+        if now < open_until || state.probing {
+            return Err(AiError::CircuitOpen {
+                retry_in: open_until.saturating_duration_since(now).max(Duration::from_secs(1)),
+            });
+        }
+
+        state.probing = true;
+        Ok(Permit { breaker: self, probe: true })
+    }
+
+    fn record_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures = 0;
+        state.open_until = None;
+        state.probing = false;
+    }
+
+    fn record_failure(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures += 1;
+        if state.probing || state.consecutive_failures >= self.threshold {
+            if state.open_until.is_none() {
+                eprintln!("AI service circuit breaker opened after {} failures", state.consecutive_failures);
+            }
+            state.open_until = Some(Instant::now() + self.cooldown);
+            state.probing = false;
+        }
+    }
+}
+
+// A request the breaker let through; its outcome must be recorded. If it is
+// dropped first, because the caller's future was cancelled mid-request, a
+// probe gives up its slot so the next request can probe instead of the
+// breaker staying half-open for good.
+struct Permit<'a> {
+    breaker: &'a CircuitBreaker,
+    probe: bool,
+}
+
+impl Permit<'_> {
+    fn success(mut self) {
+        self.probe = false;
+        self.breaker.record_success();
+    }
+
+    fn failure(mut self) {
+        self.probe = false;
+        self.breaker.record_failure();
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        if self.probe {
+            self.breaker.state.lock().unwrap().probing = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_open(result: Result<Permit<'_>, AiError>) -> bool {
+        matches!(result, Err(AiError::CircuitOpen { .. }))
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+        breaker.check().unwrap().failure();
+        breaker.check().unwrap().success();
+        breaker.check().unwrap().failure();
+        breaker.check().unwrap().failure();
+        assert!(is_open(breaker.check()));
+    }
+
+    #[test]
+    fn one_probe_at_a_time_after_the_cooldown() {
+        let breaker = CircuitBreaker::new(1, Duration::ZERO);
+        breaker.check().unwrap().failure();
+
+        let probe = breaker.check().unwrap();
+        assert!(is_open(breaker.check()));
+        probe.failure();
+
+        let probe = breaker.check().unwrap();
+        probe.success();
+        assert!(breaker.state.lock().unwrap().open_until.is_none());
+        breaker.check().unwrap().success();
+    }
+
+    #[tokio::test]
+    async fn a_cancelled_probe_frees_its_slot() {
+        let breaker = CircuitBreaker::new(1, Duration::ZERO);
+        breaker.check().unwrap().failure();
+
+        // The probe's request never finishes; the caller gives up on it
+        let probe = async {
+            let permit = breaker.check().unwrap();
+            std::future::pending::<()>().await;
+            permit.success();
+        };
+        assert!(tokio::time::timeout(Duration::from_millis(10), probe).await.is_err());
+
+        let probe = breaker.check().expect("another request may probe");
+        assert!(is_open(breaker.check()));
+        drop(probe);
+        assert!(breaker.check().is_ok());
+    }
+}
@@ -1,24 +1,34 @@
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
 use warp::Filter;
+use std::collections::HashMap;
 use std::sync::Arc;
-use warp::http::StatusCode;
 use warp::reject::Reject;
 
 use crate::config::Config;
 
+pub const ADMIN_ROLE: &str = "admin";
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: i32, // user id
     pub email: String,
+    #[serde(default)]
+    pub role: String,
     pub exp: usize,
 }
 
+impl Claims {
+    pub fn is_admin(&self) -> bool {
+        self.role == ADMIN_ROLE
+    }
+}
+
 #[derive(Debug)]
 pub struct AuthError;
 impl Reject for AuthError {}
 
-pub fn create_jwt(user_id: i32, email: &str, secret: &str) -> Result<String, jsonwebtoken::errors::Error> {
+pub fn create_jwt(user_id: i32, email: &str, role: &str, secret: &str) -> Result<String, jsonwebtoken::errors::Error> {
     let expiration = chrono::Utc::now()
         .checked_add_signed(chrono::Duration::hours(24))
         .expect("valid timestamp")
@@ -27,6 +37,7 @@ pub fn create_jwt(user_id: i32, email: &str, secret: &str) -> Result<String, jso
     let claims = Claims {
         sub: user_id,
         email: email.to_string(),
+        role: role.to_string(),
         exp: expiration,
     };
 
@@ -45,21 +56,38 @@ pub fn verify_jwt(token: &str, secret: &str) -> Result<Claims, jsonwebtoken::err
 pub fn with_auth(
     config: Arc<Config>,
 ) -> impl Filter<Extract = (Claims,), Error = warp::Rejection> + Clone {
-    warp::header::<String>("authorization")
+    // A missing header is treated the same as a bad token so anonymous
+    // callers get a 401 rather than a generic rejection.
+    warp::header::optional::<String>("authorization")
         .and(warp::any().map(move || config.clone()))
-        .and_then(|auth_header: String, config: Arc<Config>| async move {
-            if !auth_header.starts_with("Bearer ") {
-                return Err(warp::reject::custom(AuthError));
-            }
+        .and_then(|auth_header: Option<String>, config: Arc<Config>| async move {
+            let token = auth_header.as_deref().and_then(|h| h.strip_prefix("Bearer "));
+            claims_from_token(token, &config)
+        })
+}
 
-            let token = &auth_header[7..];
-            match verify_jwt(token, &config.jwt_secret) {
-                Ok(claims) => Ok(claims),
-                Err(_) => Err(warp::reject::custom(AuthError)),
-            }
+// Browsers' EventSource API can't set headers, so streaming endpoints also
+// accept the token as an `access_token` query parameter.
+pub fn with_stream_auth(
+    config: Arc<Config>,
+) -> impl Filter<Extract = (Claims,), Error = warp::Rejection> + Clone {
+    warp::header::optional::<String>("authorization")
+        .and(warp::query::<HashMap<String, String>>())
+        .and(warp::any().map(move || config.clone()))
+        .and_then(|auth_header: Option<String>, query: HashMap<String, String>, config: Arc<Config>| async move {
+            let token = auth_header
+                .as_deref()
+                .and_then(|h| h.strip_prefix("Bearer "))
+                .or(query.get("access_token").map(String::as_str));
+            claims_from_token(token, &config)
         })
 }
 
+fn claims_from_token(token: Option<&str>, config: &Config) -> Result<Claims, warp::Rejection> {
+    let token = token.ok_or_else(|| warp::reject::custom(AuthError))?;
+    verify_jwt(token, &config.jwt_secret).map_err(|_| warp::reject::custom(AuthError))
+}
+
 pub fn hash_password(password: &str) -> Result<String, bcrypt::BcryptError> {
     bcrypt::hash(password, bcrypt::DEFAULT_COST)
 }
@@ -7,6 +7,29 @@ pub struct Config {
     pub ai_service_url: String,
     pub upload_dir: String,
     pub max_file_size: u64,
+    pub max_concurrent_extractions: usize,
+    pub job_workers: usize,
+    pub job_max_attempts: i32,
+    pub ai_timeout_secs: u64,
+    pub ai_max_retries: u32,
+    pub ai_breaker_threshold: u32,
+    pub ai_breaker_cooldown_secs: u64,
+    pub ai_invalid_retries: u32,
+    pub scorer: String,
+    pub scorer_fallback: Option<String>,
+    pub ai_models: Vec<String>,
+    pub rubric_version: String,
+    pub storage: String,
+    pub s3_endpoint: String,
+    pub s3_bucket: String,
+    pub s3_region: String,
+    pub s3_access_key_id: String,
+    pub s3_secret_access_key: String,
+    pub encryption_keys: Vec<String>,
+    pub retention_days: u32,
+    pub retention_days_by_role: Vec<(String, u32)>,
+    pub retention_interval_secs: u64,
+    pub retention_dry_run: bool,
 }
 
 impl Config {
@@ -24,6 +47,104 @@ impl Config {
                 .unwrap_or_else(|_| "10485760".to_string())
                 .parse()
                 .expect("MAX_FILE_SIZE must be a valid number"),
+            max_concurrent_extractions: env::var("MAX_CONCURRENT_EXTRACTIONS")
+                .unwrap_or_else(|_| "4".to_string())
+                .parse()
+                .expect("MAX_CONCURRENT_EXTRACTIONS must be a valid number"),
+            job_workers: env::var("JOB_WORKERS")
+                .unwrap_or_else(|_| "4".to_string())
+                .parse()
+                .expect("JOB_WORKERS must be a valid number"),
+            job_max_attempts: env::var("JOB_MAX_ATTEMPTS")
+                .unwrap_or_else(|_| "3".to_string())
+                .parse()
+                .expect("JOB_MAX_ATTEMPTS must be a valid number"),
+            ai_timeout_secs: env::var("AI_TIMEOUT_SECS")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .expect("AI_TIMEOUT_SECS must be a valid number"),
+            ai_max_retries: env::var("AI_MAX_RETRIES")
+                .unwrap_or_else(|_| "2".to_string())
+                .parse()
+                .expect("AI_MAX_RETRIES must be a valid number"),
+            ai_breaker_threshold: env::var("AI_BREAKER_THRESHOLD")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()
+                .expect("AI_BREAKER_THRESHOLD must be a valid number"),
+            ai_breaker_cooldown_secs: env::var("AI_BREAKER_COOLDOWN_SECS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .expect("AI_BREAKER_COOLDOWN_SECS must be a valid number"),
+            ai_invalid_retries: env::var("AI_INVALID_RETRIES")
+                .unwrap_or_else(|_| "1".to_string())
+                .parse()
+                .expect("AI_INVALID_RETRIES must be a valid number"),
+            scorer: env::var("SCORER")
+                .unwrap_or_else(|_| "ai".to_string()),
+            // "none" disables the fallback
+            scorer_fallback: Some(env::var("SCORER_FALLBACK").unwrap_or_else(|_| "heuristic".to_string()))
+                .filter(|name| name != "none"),
+            // Models a critique may request instead of the AI service's
+            // default; empty disables the choice
+            ai_models: env::var("AI_MODELS")
+                .unwrap_or_default()
+                .split(',')
+                .map(|m| m.trim().to_string())
+                .filter(|m| !m.is_empty())
+                .collect(),
+            // Bump when the scoring prompt or rules change so cached
+            // critiques from the old rubric are no longer reused
+            rubric_version: env::var("RUBRIC_VERSION")
+                .unwrap_or_else(|_| "1".to_string()),
+            // Where uploaded files are kept: "local" (UPLOAD_DIR) or "s3".
+            // UPLOAD_DIR also holds uploads in flight with either.
+            storage: env::var("STORAGE")
+                .unwrap_or_else(|_| "local".to_string()),
+            s3_endpoint: env::var("S3_ENDPOINT").unwrap_or_default(),
+            s3_bucket: env::var("S3_BUCKET").unwrap_or_default(),
+            s3_region: env::var("S3_REGION")
+                .unwrap_or_else(|_| "us-east-1".to_string()),
+            s3_access_key_id: env::var("S3_ACCESS_KEY_ID").unwrap_or_default(),
+            s3_secret_access_key: env::var("S3_SECRET_ACCESS_KEY").unwrap_or_default(),
+            // Master keys as <id>:<base64 32 bytes>, newest first; empty
+            // stores uploads unencrypted
+            encryption_keys: env::var("ENCRYPTION_KEYS")
+                .unwrap_or_default()
+                .split(',')
+                .map(|k| k.trim().to_string())
+                .filter(|k| !k.is_empty())
+                .collect(),
+            // Days a resume is kept after upload; 0 keeps resumes forever
+            retention_days: env::var("RETENTION_DAYS")
+                .unwrap_or_else(|_| "0".to_string())
+                .parse()
+                .expect("RETENTION_DAYS must be a valid number"),
+            // Per-role overrides of RETENTION_DAYS as <role>:<days>
+            retention_days_by_role: env::var("RETENTION_DAYS_BY_ROLE")
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|entry| !entry.is_empty())
+                .map(|entry| {
+                    let (role, days) = entry
+                        .split_once(':')
+                        .expect("RETENTION_DAYS_BY_ROLE entries must be <role>:<days>");
+                    let days = days
+                        .trim()
+                        .parse()
+                        .expect("RETENTION_DAYS_BY_ROLE days must be a valid number");
+                    (role.trim().to_string(), days)
+                })
+                .collect(),
+            retention_interval_secs: env::var("RETENTION_INTERVAL_SECS")
+                .unwrap_or_else(|_| "3600".to_string())
+                .parse()
+                .expect("RETENTION_INTERVAL_SECS must be a valid number"),
+            // Logs what the purge would remove without removing it
+            retention_dry_run: env::var("RETENTION_DRY_RUN")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .expect("RETENTION_DRY_RUN must be true or false"),
         }
     }
 }
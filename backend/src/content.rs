@@ -0,0 +1,335 @@
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Cursor, Read};
+use std::path::{Path, PathBuf};
+use tokio::sync::Semaphore;
+use warp::http::StatusCode;
+
+use crate::crypto::Opener;
+use crate::docx::{self, DocxError};
+use crate::pdf::{self, PdfError};
+use crate::upload::ContentReader;
+
+pub const PDF_MIME_TYPE: &str = "application/pdf";
+pub const DOCX_MIME_TYPE: &str = "application/vnd.openxmlformats-officedocument.wordprocessingml.document";
+pub const TEXT_MIME_TYPE: &str = "text/plain";
+
+const OLE_MAGIC: &[u8] = &[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    Pdf,
+    Docx,
+    PlainText,
+}
+
+impl FileKind {
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            FileKind::Pdf => PDF_MIME_TYPE,
+            FileKind::Docx => DOCX_MIME_TYPE,
+            FileKind::PlainText => TEXT_MIME_TYPE,
+        }
+    }
+
+    fn from_mime_type(mime_type: &str) -> Option<Self> {
+        match mime_type {
+            PDF_MIME_TYPE | "application/x-pdf" => Some(FileKind::Pdf),
+            DOCX_MIME_TYPE => Some(FileKind::Docx),
+            TEXT_MIME_TYPE | "text/markdown" => Some(FileKind::PlainText),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ContentError {
+    UnsupportedType(String),
+    TypeMismatch { declared: FileKind, detected: FileKind },
+    InvalidText,
+    EmptyText,
+    Pdf(PdfError),
+    Docx(DocxError),
+}
+
+impl ContentError {
+    // Stable identifier for clients to branch on; the message may change.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ContentError::UnsupportedType(_) | ContentError::Docx(DocxError::NotWordDocument) => "unsupported_file_type",
+            ContentError::TypeMismatch { .. } => "file_type_mismatch",
+            ContentError::InvalidText => "invalid_text_encoding",
+            ContentError::EmptyText => "empty_text",
+            ContentError::Pdf(PdfError::Encrypted) => "pdf_encrypted",
+            ContentError::Pdf(PdfError::ImageOnly) => "pdf_no_text",
+            ContentError::Pdf(PdfError::Malformed(_)) => "pdf_malformed",
+            ContentError::Docx(DocxError::Empty) => "docx_no_text",
+            ContentError::Docx(DocxError::Malformed(_)) => "docx_malformed",
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ContentError::UnsupportedType(_) | ContentError::TypeMismatch { .. } => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            _ => StatusCode::UNPROCESSABLE_ENTITY,
+        }
+    }
+}
+
+impl fmt::Display for ContentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContentError::UnsupportedType(what) => write!(f, "Unsupported file type: {}; upload a PDF, DOCX or plain text file", what),
+            ContentError::TypeMismatch { declared, detected } => write!(
+                f,
+                "File was sent as {} but its contents are {}",
+                declared.mime_type(),
+                detected.mime_type()
+            ),
+            ContentError::InvalidText => write!(f, "Text file is not valid UTF-8"),
+            ContentError::EmptyText => write!(f, "Text file is empty"),
+            ContentError::Pdf(e) => write!(f, "{}", e),
+            ContentError::Docx(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ContentError {}
+
+// Determines the file kind from its leading bytes, independent of whatever
+// content type the client claimed. `data` may be only the head of the file:
+// a zip is taken to be a Word document until extraction finds otherwise, and
+// text may end partway through a character.
+pub fn sniff(data: &[u8]) -> Result<FileKind, ContentError> {
+    let start = data.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(data.len());
+    if data[start..].starts_with(b"%PDF-") {
+        return Ok(FileKind::Pdf);
+    }
+
+    if data.starts_with(ZIP_MAGIC) {
+        // DOCX, XLSX, ODT and plain archives all share the zip signature; the
+        // archive's directory is at its end, so docx::extract_text tells them
+        // apart.
+        return Ok(FileKind::Docx);
+    }
+
+    if data.starts_with(OLE_MAGIC) {
+        return Err(ContentError::UnsupportedType(
+            "legacy Word (.doc) or password-protected Office document".to_string(),
+        ));
+    }
+
+    let text = data.strip_prefix(UTF8_BOM).unwrap_or(data);
+    let utf8 = match std::str::from_utf8(text) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    };
+    if !text.contains(&0) && utf8 {
+        return Ok(FileKind::PlainText);
+    }
+
+    Err(ContentError::UnsupportedType("binary file".to_string()))
+}
+
+// Checks the uploaded bytes against the client-supplied content type. A
+// missing or generic type defers to what was sniffed; a specific type that
+// disagrees with the bytes is rejected.
+pub fn validate(declared: Option<&str>, data: &[u8]) -> Result<FileKind, ContentError> {
+    let detected = sniff(data)?;
+
+    let declared = declared
+        .map(|ct| ct.split(';').next().unwrap_or("").trim().to_ascii_lowercase())
+        .filter(|ct| !ct.is_empty() && ct != "application/octet-stream");
+
+    match declared {
+        None => Ok(detected),
+        Some(ct) => match FileKind::from_mime_type(&ct) {
+            Some(kind) if kind == detected => Ok(detected),
+            Some(kind) => Err(ContentError::TypeMismatch { declared: kind, detected }),
+            None => Err(ContentError::UnsupportedType(ct)),
+        },
+    }
+}
+
+// Extracts text from staged uploads, a few at a time. lopdf only parses a
+// PDF from a complete copy in memory, and a sealed DOCX is opened into memory
+// because the zip reader seeks, so each extraction may hold the whole file
+// (twice, counting the parsed document); the permits bound that to
+// MAX_CONCURRENT_EXTRACTIONS uploads. An unsealed DOCX is read from disk.
+pub struct Extractor {
+    permits: Semaphore,
+}
+
+impl Extractor {
+    pub fn new(limit: usize) -> Self {
+        Self { permits: Semaphore::new(limit.max(1)) }
+    }
+
+    // Parsing is CPU bound and reads the file with blocking I/O, so it runs
+    // off the async workers. A panic inside a parser on a malformed file
+    // surfaces as a join error. The outer error is a failure to read the
+    // staged file; the inner one is about its contents.
+    pub async fn extract_text(
+        &self,
+        kind: FileKind,
+        path: PathBuf,
+        opener: Option<Opener>,
+    ) -> io::Result<Result<String, ContentError>> {
+        let _permit = self.permits.acquire().await.map_err(io::Error::other)?;
+        match tokio::task::spawn_blocking(move || extract(kind, &path, opener)).await {
+            Ok(result) => result,
+            Err(e) => match kind {
+                FileKind::Pdf => Ok(Err(ContentError::Pdf(PdfError::Malformed(e.to_string())))),
+                FileKind::Docx => Ok(Err(ContentError::Docx(DocxError::Malformed(e.to_string())))),
+                FileKind::PlainText => Err(io::Error::other(e)),
+            },
+        }
+    }
+}
+
+fn extract(kind: FileKind, path: &Path, opener: Option<Opener>) -> io::Result<Result<String, ContentError>> {
+    let result = match kind {
+        FileKind::Pdf => pdf::extract_text(&read_all(path, opener)?).map_err(ContentError::Pdf),
+        FileKind::Docx => {
+            let text = match opener {
+                None => docx::extract_text(File::open(path)?),
+                Some(opener) => docx::extract_text(Cursor::new(read_all(path, Some(opener))?)),
+            };
+            text.map_err(|e| match e {
+                DocxError::NotWordDocument => ContentError::UnsupportedType("zip archive that is not a Word document".to_string()),
+                e => ContentError::Docx(e),
+            })
+        }
+        FileKind::PlainText => plain_text(read_all(path, opener)?),
+    };
+    Ok(result)
+}
+
+fn read_all(path: &Path, opener: Option<Opener>) -> io::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    ContentReader::open(path, opener)?.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+fn plain_text(data: Vec<u8>) -> Result<String, ContentError> {
+    if data.contains(&0) {
+        return Err(ContentError::UnsupportedType("binary file".to_string()));
+    }
+    let mut text = String::from_utf8(data).map_err(|_| ContentError::InvalidText)?;
+    if text.starts_with('\u{feff}') {
+        text.remove(0);
+    }
+    if text.trim().is_empty() {
+        return Err(ContentError::EmptyText);
+    }
+    Ok(text)
+}
+
+// Identifies a resume by its extracted text, ignoring layout whitespace, so
+// the same resume saved or exported again is still recognised.
+pub fn text_hash(text: &str) -> String {
+    let normalised: Vec<String> = text
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect();
+    format!("{:x}", Sha256::digest(normalised.join("\n").as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use warp::Reply;
+
+    const PDF: &[u8] = b"%PDF-1.7\n%\xE2\xE3\xCF\xD3\n1 0 obj";
+
+    fn zip(name: &str) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        writer.start_file(name, zip::write::SimpleFileOptions::default()).unwrap();
+        writer.write_all(b"a,b\n1,2\n").unwrap();
+        writer.finish().unwrap().into_inner()
+    }
+
+    // The status and body a client would see for `err`.
+    async fn reply(err: &ContentError) -> (StatusCode, serde_json::Value) {
+        let response = crate::handlers::content_error_reply(err).into_response();
+        let status = response.status();
+        let body = warp::hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[test]
+    fn sniffs_magic_bytes() {
+        assert_eq!(sniff(PDF).unwrap(), FileKind::Pdf);
+        assert_eq!(sniff(b"\r\n  %PDF-1.4").unwrap(), FileKind::Pdf);
+        assert_eq!(sniff(&zip("word/document.xml")).unwrap(), FileKind::Docx);
+        assert_eq!(sniff("Jane Doe\nBackend engineer".as_bytes()).unwrap(), FileKind::PlainText);
+        assert_eq!(sniff(b"\xEF\xBB\xBFJos\xC3\xA9").unwrap(), FileKind::PlainText);
+        // A head cut off partway through "é" is still text
+        assert_eq!(sniff(b"Jos\xC3").unwrap(), FileKind::PlainText);
+    }
+
+    #[test]
+    fn generic_types_defer_to_the_bytes() {
+        assert_eq!(validate(None, PDF).unwrap(), FileKind::Pdf);
+        assert_eq!(validate(Some("application/octet-stream"), PDF).unwrap(), FileKind::Pdf);
+        assert_eq!(validate(Some("Application/PDF; name=cv.pdf"), PDF).unwrap(), FileKind::Pdf);
+        assert_eq!(validate(Some("text/markdown"), b"# Jane Doe").unwrap(), FileKind::PlainText);
+    }
+
+    #[tokio::test]
+    async fn declared_type_must_match_the_bytes() {
+        let err = validate(Some(TEXT_MIME_TYPE), PDF).unwrap_err();
+        assert!(matches!(err, ContentError::TypeMismatch { declared: FileKind::PlainText, detected: FileKind::Pdf }));
+        let (status, body) = reply(&err).await;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(body["code"], "file_type_mismatch");
+    }
+
+    #[tokio::test]
+    async fn binary_labelled_as_text_is_unsupported() {
+        let err = validate(Some(TEXT_MIME_TYPE), b"\x7FELF\x02\x01\x01\x00\x00\x00").unwrap_err();
+        assert!(matches!(err, ContentError::UnsupportedType(_)));
+        let (status, body) = reply(&err).await;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(body["code"], "unsupported_file_type");
+
+        let err = validate(None, &OLE_MAGIC.repeat(4)).unwrap_err();
+        assert_eq!(reply(&err).await.1["code"], "unsupported_file_type");
+    }
+
+    #[tokio::test]
+    async fn unknown_declared_types_are_unsupported() {
+        let err = validate(Some("image/png"), b"plain words").unwrap_err();
+        let (status, body) = reply(&err).await;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(body["code"], "unsupported_file_type");
+    }
+
+    #[tokio::test]
+    async fn zips_that_are_not_word_documents_are_unsupported() {
+        let data = zip("sheet.csv");
+        assert_eq!(validate(Some(DOCX_MIME_TYPE), &data).unwrap(), FileKind::Docx);
+
+        let path = std::env::temp_dir().join(format!("content-test-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&path, &data).unwrap();
+        let result = Extractor::new(1).extract_text(FileKind::Docx, path.clone(), None).await;
+        std::fs::remove_file(&path).unwrap();
+
+        let err = result.unwrap().unwrap_err();
+        let (status, body) = reply(&err).await;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(body["code"], "unsupported_file_type");
+    }
+
+    #[test]
+    fn plain_text_drops_the_bom() {
+        assert_eq!(plain_text(b"\xEF\xBB\xBFJane".to_vec()).unwrap(), "Jane");
+        assert!(matches!(plain_text(b" \n ".to_vec()), Err(ContentError::EmptyText)));
+        assert!(matches!(plain_text(b"Jos\xC3".to_vec()), Err(ContentError::InvalidText)));
+    }
+}
@@ -0,0 +1,439 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use bytes::Bytes;
+use futures_util::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use std::fmt;
+use std::io;
+
+use crate::config::Config;
+
+const KEY_LEN: usize = 32;
+const TAG_LEN: usize = 16;
+// Plaintext bytes per sealed segment of a stored file.
+const SEGMENT_LEN: usize = 64 * 1024;
+const FILE_MAGIC: &[u8; 4] = b"RCE1";
+const PREFIX_LEN: usize = NONCE_LEN - 5;
+const FILE_HEADER_LEN: usize = FILE_MAGIC.len() + PREFIX_LEN;
+const FILE_AAD: &[u8] = b"file";
+const TEXT_AAD: &[u8] = b"text";
+
+#[derive(Debug)]
+pub enum CryptoError {
+    UnknownKey(String),
+    Corrupt,
+}
+
+impl fmt::Display for CryptoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CryptoError::UnknownKey(id) => write!(f, "Encryption key '{}' is not in ENCRYPTION_KEYS", id),
+            CryptoError::Corrupt => write!(f, "Encrypted data is corrupt or was sealed with another key"),
+        }
+    }
+}
+
+impl std::error::Error for CryptoError {}
+
+impl From<CryptoError> for io::Error {
+    fn from(e: CryptoError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, e)
+    }
+}
+
+// The master keys from ENCRYPTION_KEYS. The first wraps the data keys of new
+// resumes; the others are kept so data wrapped before a rotation can still
+// be read until `reencrypt` has moved it to the first.
+pub struct Keyring {
+    keys: Vec<(String, LessSafeKey)>,
+}
+
+impl Keyring {
+    pub fn from_config(config: &Config) -> Self {
+        let keys = config
+            .encryption_keys
+            .iter()
+            .map(|entry| {
+                let (id, material) = entry
+                    .split_once(':')
+                    .unwrap_or_else(|| panic!("ENCRYPTION_KEYS entries must be <id>:<base64 key>"));
+                if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                    panic!("Invalid encryption key id '{}'; use letters, digits, '-' and '_'", id);
+                }
+                let material = STANDARD
+                    .decode(material)
+                    .ok()
+                    .filter(|bytes| bytes.len() == KEY_LEN)
+                    .unwrap_or_else(|| panic!("Encryption key '{}' must be {} bytes, base64 encoded", id, KEY_LEN));
+                (id.to_string(), aead_key(&material))
+            })
+            .collect();
+        Self { keys }
+    }
+
+    // None when encryption is off.
+    pub fn active_key_id(&self) -> Option<&str> {
+        self.keys.first().map(|(id, _)| id.as_str())
+    }
+
+    // A fresh data key for one resume, or None when encryption is off.
+    pub fn new_data_key(&self) -> Option<DataKey> {
+        let mut bytes = [0u8; KEY_LEN];
+        fill_random(&mut bytes);
+        self.wrap(bytes)
+    }
+
+    // Wraps an existing data key under the active master key.
+    pub fn rotate(&self, key: &DataKey) -> Option<DataKey> {
+        self.wrap(key.bytes)
+    }
+
+    // Unwraps a data key stored with a resume.
+    pub fn data_key(&self, key_id: &str, wrapped: &[u8]) -> Result<DataKey, CryptoError> {
+        let (_, master) = self
+            .keys
+            .iter()
+            .find(|(id, _)| id == key_id)
+            .ok_or_else(|| CryptoError::UnknownKey(key_id.to_string()))?;
+        let bytes = open(master, wrapped, key_id.as_bytes())?;
+        Ok(DataKey {
+            bytes: bytes.try_into().map_err(|_| CryptoError::Corrupt)?,
+            key_id: key_id.to_string(),
+            wrapped: wrapped.to_vec(),
+        })
+    }
+
+    // Extracted text as stored: sealed when the resume has a data key,
+    // plaintext for resumes uploaded before encryption was turned on.
+    pub fn open_text(&self, key_id: Option<&str>, wrapped: Option<&[u8]>, stored: String) -> Result<String, CryptoError> {
+        match (key_id, wrapped) {
+            (Some(key_id), Some(wrapped)) => self.data_key(key_id, wrapped)?.open_text(&stored),
+            _ => Ok(stored),
+        }
+    }
+
+    fn wrap(&self, bytes: [u8; KEY_LEN]) -> Option<DataKey> {
+        let (key_id, master) = self.keys.first()?;
+        Some(DataKey {
+            bytes,
+            key_id: key_id.clone(),
+            wrapped: seal(master, bytes.to_vec(), key_id.as_bytes()),
+        })
+    }
+}
+
+// The key a single resume's file and text are sealed with, together with
+// its wrapped form and the id of the master key that wrapped it.
+pub struct DataKey {
+    bytes: [u8; KEY_LEN],
+    key_id: String,
+    wrapped: Vec<u8>,
+}
+
+impl DataKey {
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    pub fn wrapped(&self) -> &[u8] {
+        &self.wrapped
+    }
+
+    pub fn seal_text(&self, text: &str) -> String {
+        STANDARD.encode(seal(&aead_key(&self.bytes), text.as_bytes().to_vec(), TEXT_AAD))
+    }
+
+    pub fn open_text(&self, stored: &str) -> Result<String, CryptoError> {
+        let sealed = STANDARD.decode(stored).map_err(|_| CryptoError::Corrupt)?;
+        let text = open(&aead_key(&self.bytes), &sealed, TEXT_AAD)?;
+        String::from_utf8(text).map_err(|_| CryptoError::Corrupt)
+    }
+
+    pub fn sealer(&self) -> Sealer {
+        let mut prefix = [0u8; PREFIX_LEN];
+        fill_random(&mut prefix);
+        Sealer {
+            key: aead_key(&self.bytes),
+            prefix,
+            counter: 0,
+            buffer: Vec::new(),
+            header_written: false,
+        }
+    }
+
+    pub fn opener(&self) -> Opener {
+        Opener {
+            key: aead_key(&self.bytes),
+            prefix: None,
+            counter: 0,
+            buffer: Vec::new(),
+        }
+    }
+}
+
+// Seals a file as it streams: a header with a random nonce prefix, then
+// SEGMENT_LEN segments each with its own tag. The last segment is marked in
+// its nonce, so a truncated file fails to open rather than reading short.
+pub struct Sealer {
+    key: LessSafeKey,
+    prefix: [u8; PREFIX_LEN],
+    counter: u32,
+    buffer: Vec<u8>,
+    header_written: bool,
+}
+
+impl Sealer {
+    // Returns the ciphertext ready so far.
+    pub fn update(&mut self, data: &[u8]) -> Vec<u8> {
+        let mut out = self.header();
+        self.buffer.extend_from_slice(data);
+        while self.buffer.len() > SEGMENT_LEN {
+            let rest = self.buffer.split_off(SEGMENT_LEN);
+            let segment = std::mem::replace(&mut self.buffer, rest);
+            out.extend(self.seal(segment, false));
+        }
+        out
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        let mut out = self.header();
+        let segment = std::mem::take(&mut self.buffer);
+        out.extend(self.seal(segment, true));
+        out
+    }
+
+    fn header(&mut self) -> Vec<u8> {
+        if std::mem::replace(&mut self.header_written, true) {
+            return Vec::new();
+        }
+        [&FILE_MAGIC[..], &self.prefix].concat()
+    }
+
+    fn seal(&mut self, mut segment: Vec<u8>, last: bool) -> Vec<u8> {
+        let nonce = segment_nonce(&self.prefix, self.counter, last);
+        self.counter += 1;
+        self.key
+            .seal_in_place_append_tag(nonce, Aad::from(FILE_AAD), &mut segment)
+            .expect("segments are far below the AES-GCM length limit");
+        segment
+    }
+}
+
+// The reverse of Sealer.
+pub struct Opener {
+    key: LessSafeKey,
+    prefix: Option<[u8; PREFIX_LEN]>,
+    counter: u32,
+    buffer: Vec<u8>,
+}
+
+impl Opener {
+    // Returns the plaintext ready so far.
+    pub fn update(&mut self, data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        self.buffer.extend_from_slice(data);
+        if self.prefix.is_none() {
+            if self.buffer.len() < FILE_HEADER_LEN {
+                return Ok(Vec::new());
+            }
+            if !self.buffer.starts_with(FILE_MAGIC) {
+                return Err(CryptoError::Corrupt);
+            }
+            let header: Vec<u8> = self.buffer.drain(..FILE_HEADER_LEN).collect();
+            self.prefix = Some(header[FILE_MAGIC.len()..].try_into().map_err(|_| CryptoError::Corrupt)?);
+        }
+
+        let mut out = Vec::new();
+        while self.buffer.len() > SEGMENT_LEN + TAG_LEN {
+            let rest = self.buffer.split_off(SEGMENT_LEN + TAG_LEN);
+            let segment = std::mem::replace(&mut self.buffer, rest);
+            out.extend(self.open(segment, false)?);
+        }
+        Ok(out)
+    }
+
+    pub fn finish(mut self) -> Result<Vec<u8>, CryptoError> {
+        let segment = std::mem::take(&mut self.buffer);
+        self.open(segment, true)
+    }
+
+    fn open(&mut self, mut segment: Vec<u8>, last: bool) -> Result<Vec<u8>, CryptoError> {
+        let prefix = self.prefix.ok_or(CryptoError::Corrupt)?;
+        let nonce = segment_nonce(&prefix, self.counter, last);
+        self.counter += 1;
+        let len = self
+            .key
+            .open_in_place(nonce, Aad::from(FILE_AAD), &mut segment)
+            .map_err(|_| CryptoError::Corrupt)?
+            .len();
+        segment.truncate(len);
+        Ok(segment)
+    }
+}
+
+// Decrypts a stored file as it streams.
+pub fn decrypt_stream(
+    opener: Opener,
+    sealed: BoxStream<'static, io::Result<Bytes>>,
+) -> BoxStream<'static, io::Result<Bytes>> {
+    stream::try_unfold((sealed, Some(opener)), |(mut sealed, opener)| async move {
+        let Some(mut opener) = opener else {
+            return Ok(None);
+        };
+        while let Some(chunk) = sealed.try_next().await? {
+            let plain = opener.update(&chunk)?;
+            if !plain.is_empty() {
+                return Ok(Some((Bytes::from(plain), (sealed, Some(opener)))));
+            }
+        }
+        let plain = opener.finish()?;
+        Ok(Some((Bytes::from(plain), (sealed, None))))
+    })
+    .boxed()
+}
+
+// The plaintext length of a sealed file of `sealed_len` bytes.
+pub fn plaintext_len(sealed_len: u64) -> Option<u64> {
+    let body = sealed_len.checked_sub(FILE_HEADER_LEN as u64)?;
+    let segments = body.div_ceil((SEGMENT_LEN + TAG_LEN) as u64).max(1);
+    body.checked_sub(segments * TAG_LEN as u64)
+}
+
+fn segment_nonce(prefix: &[u8; PREFIX_LEN], counter: u32, last: bool) -> Nonce {
+    let mut nonce = [0u8; NONCE_LEN];
+    nonce[..PREFIX_LEN].copy_from_slice(prefix);
+    nonce[PREFIX_LEN..NONCE_LEN - 1].copy_from_slice(&counter.to_be_bytes());
+    nonce[NONCE_LEN - 1] = last as u8;
+    Nonce::assume_unique_for_key(nonce)
+}
+
+fn aead_key(bytes: &[u8]) -> LessSafeKey {
+    LessSafeKey::new(UnboundKey::new(&AES_256_GCM, bytes).expect("keys are KEY_LEN bytes"))
+}
+
+fn fill_random(bytes: &mut [u8]) {
+    SystemRandom::new().fill(bytes).expect("the system random source is available");
+}
+
+// A random nonce followed by the ciphertext and tag.
+fn seal(key: &LessSafeKey, mut data: Vec<u8>, aad: &[u8]) -> Vec<u8> {
+    let mut nonce = [0u8; NONCE_LEN];
+    fill_random(&mut nonce);
+    key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(aad), &mut data)
+        .expect("sealed values are far below the AES-GCM length limit");
+    [&nonce[..], &data].concat()
+}
+
+fn open(key: &LessSafeKey, sealed: &[u8], aad: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    if sealed.len() < NONCE_LEN + TAG_LEN {
+        return Err(CryptoError::Corrupt);
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| CryptoError::Corrupt)?;
+    let mut data = ciphertext.to_vec();
+    let len = key.open_in_place(nonce, Aad::from(aad), &mut data).map_err(|_| CryptoError::Corrupt)?.len();
+    data.truncate(len);
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEGMENT: usize = SEGMENT_LEN + TAG_LEN;
+
+    fn data_key(byte: u8) -> DataKey {
+        DataKey { bytes: [byte; KEY_LEN], key_id: "test".to_string(), wrapped: Vec::new() }
+    }
+
+    fn plaintext(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    // Feeds the sealer and opener in odd-sized chunks so segments straddle them
+    fn seal_file(key: &DataKey, data: &[u8]) -> Vec<u8> {
+        let mut sealer = key.sealer();
+        let mut sealed: Vec<u8> = data.chunks(7919).flat_map(|chunk| sealer.update(chunk)).collect();
+        sealed.extend(sealer.finish());
+        sealed
+    }
+
+    fn open_file(key: &DataKey, sealed: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let mut opener = key.opener();
+        let mut data = Vec::new();
+        for chunk in sealed.chunks(6007) {
+            data.extend(opener.update(chunk)?);
+        }
+        data.extend(opener.finish()?);
+        Ok(data)
+    }
+
+    #[test]
+    fn files_round_trip_at_segment_boundaries() {
+        let key = data_key(1);
+        for len in [0, 1, SEGMENT_LEN - 1, SEGMENT_LEN, SEGMENT_LEN + 1, 3 * SEGMENT_LEN] {
+            let data = plaintext(len);
+            let sealed = seal_file(&key, &data);
+            assert_eq!(sealed.len(), FILE_HEADER_LEN + len + len.div_ceil(SEGMENT_LEN).max(1) * TAG_LEN);
+            assert_eq!(plaintext_len(sealed.len() as u64), Some(len as u64));
+            assert_eq!(open_file(&key, &sealed).unwrap(), data, "length {}", len);
+        }
+    }
+
+    #[test]
+    fn truncated_files_fail_to_open() {
+        let key = data_key(1);
+        let sealed = seal_file(&key, &plaintext(2 * SEGMENT_LEN + 100));
+
+        // Whole segments dropped from the end, a partial last segment, and
+        // nothing past the header
+        for len in [FILE_HEADER_LEN + SEGMENT, FILE_HEADER_LEN + 2 * SEGMENT, sealed.len() - 1, FILE_HEADER_LEN] {
+            assert!(open_file(&key, &sealed[..len]).is_err(), "opened {} of {} bytes", len, sealed.len());
+        }
+    }
+
+    #[test]
+    fn reordered_segments_fail_to_open() {
+        let key = data_key(1);
+        let sealed = seal_file(&key, &plaintext(2 * SEGMENT_LEN + 100));
+        let (header, body) = sealed.split_at(FILE_HEADER_LEN);
+        let segments: Vec<&[u8]> = body.chunks(SEGMENT).collect();
+
+        for order in [[1, 0, 2], [0, 2, 1], [2, 1, 0]] {
+            let reordered = [header, segments[order[0]], segments[order[1]], segments[order[2]]].concat();
+            assert!(open_file(&key, &reordered).is_err(), "opened segments in order {:?}", order);
+        }
+    }
+
+    #[test]
+    fn files_only_open_with_their_key() {
+        let sealed = seal_file(&data_key(1), b"resume");
+        assert!(open_file(&data_key(2), &sealed).is_err());
+
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(open_file(&data_key(1), &tampered).is_err());
+    }
+
+    #[test]
+    fn text_round_trips() {
+        let key = data_key(1);
+        let stored = key.seal_text("Jane Doe\nBackend engineer");
+        assert_eq!(key.open_text(&stored).unwrap(), "Jane Doe\nBackend engineer");
+        assert!(data_key(2).open_text(&stored).is_err());
+    }
+
+    #[test]
+    fn data_keys_unwrap_only_under_their_master_key() {
+        let keyring = Keyring {
+            keys: vec![("new".to_string(), aead_key(&[1; KEY_LEN])), ("old".to_string(), aead_key(&[2; KEY_LEN]))],
+        };
+        let key = keyring.new_data_key().unwrap();
+        assert_eq!(key.key_id(), "new");
+
+        let unwrapped = keyring.data_key("new", key.wrapped()).unwrap();
+        assert_eq!(unwrapped.bytes, key.bytes);
+        assert!(keyring.data_key("old", key.wrapped()).is_err());
+        assert!(matches!(keyring.data_key("gone", key.wrapped()), Err(CryptoError::UnknownKey(_))));
+    }
+}
@@ -0,0 +1,244 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+
+use crate::validation::CATEGORIES;
+
+// Two resumes that differ in more lines than this are reported as one
+// wholesale replacement; the diff would be noise and costly to compute.
+const MAX_EDITS: usize = 2000;
+// Texts longer than this are not diffed at all.
+pub const MAX_LINES: usize = 5000;
+
+// Word overlap above which two suggestions are taken to say the same thing.
+const SIMILARITY: f64 = 0.6;
+
+#[derive(Debug, Serialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Equal,
+    Added,
+    Removed,
+}
+
+// A run of consecutive lines with the same change.
+#[derive(Debug, Serialize)]
+pub struct DiffChunk {
+    pub kind: ChangeKind,
+    pub lines: Vec<String>,
+}
+
+// Line diff of two texts (Myers' algorithm), as alternating chunks. None
+// when either text has more than MAX_LINES lines.
+pub fn line_diff(before: &str, after: &str) -> Option<Vec<DiffChunk>> {
+    let a: Vec<&str> = before.lines().collect();
+    let b: Vec<&str> = after.lines().collect();
+    if a.len() > MAX_LINES || b.len() > MAX_LINES {
+        return None;
+    }
+
+    let edits = myers(&a, &b).unwrap_or_else(|| {
+        a.iter()
+            .map(|line| (ChangeKind::Removed, *line))
+            .chain(b.iter().map(|line| (ChangeKind::Added, *line)))
+            .collect()
+    });
+
+    let mut chunks: Vec<DiffChunk> = Vec::new();
+    for (kind, line) in edits {
+        match chunks.last_mut() {
+            Some(chunk) if chunk.kind == kind => chunk.lines.push(line.to_string()),
+            _ => chunks.push(DiffChunk {
+                kind,
+                lines: vec![line.to_string()],
+            }),
+        }
+    }
+    Some(chunks)
+}
+
+// Returns None when the texts need more than MAX_EDITS insertions and
+// deletions. Memory is bounded by MAX_EDITS rather than by the length of
+// the texts.
+fn myers<'a>(a: &[&'a str], b: &[&'a str]) -> Option<Vec<(ChangeKind, &'a str)>> {
+    let n = a.len() as isize;
+    let m = b.len() as isize;
+    let max = (a.len() + b.len()).min(MAX_EDITS) as isize;
+    let offset = max + 1;
+    let index = |k: isize| (k + offset) as usize;
+
+    // v[k] is the furthest x reached on diagonal k. Before step d only
+    // diagonals -(d-1)..=d-1 have been reached, so that window of v is all
+    // the snapshot for d needs to walk the path back.
+    let mut v = vec![0isize; 2 * max as usize + 3];
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+
+    'search: for d in 0..=max {
+        trace.push(if d == 0 { Vec::new() } else { v[index(1 - d)..=index(d - 1)].to_vec() });
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && v[index(k - 1)] < v[index(k + 1)]) {
+                v[index(k + 1)]
+            } else {
+                v[index(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[index(k)] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+        if d as usize == MAX_EDITS {
+            return None;
+        }
+    }
+
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, snapshot) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let (prev_x, prev_y) = if d == 0 {
+            (0, 0)
+        } else {
+            let v = |k: isize| snapshot[(k + d - 1) as usize];
+            let k = x - y;
+            let prev_k = if k == -d || (k != d && v(k - 1) < v(k + 1)) { k + 1 } else { k - 1 };
+            (v(prev_k), v(prev_k) - prev_k)
+        };
+
+        while x > prev_x && y > prev_y {
+            edits.push((ChangeKind::Equal, a[(x - 1) as usize]));
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            if x == prev_x {
+                edits.push((ChangeKind::Added, b[(y - 1) as usize]));
+            } else {
+                edits.push((ChangeKind::Removed, a[(x - 1) as usize]));
+            }
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+
+    edits.reverse();
+    Some(edits)
+}
+
+// Suggestions per category: those from the earlier critique that no longer
+// appear (resolved), those only in the later one (new), and those in both.
+#[derive(Debug, Serialize)]
+pub struct SuggestionChanges {
+    pub resolved: Value,
+    pub new: Value,
+    pub persisting: Value,
+}
+
+// Model wording varies between runs, so suggestions are matched by word
+// overlap rather than exact text.
+pub fn compare_suggestions(before: &Value, after: &Value) -> SuggestionChanges {
+    let mut resolved = Map::new();
+    let mut new = Map::new();
+    let mut persisting = Map::new();
+
+    for category in CATEGORIES {
+        let old = suggestions(before, category);
+        let current = suggestions(after, category);
+
+        let still_present = |s: &Value| current.iter().any(|c| similar(s, c));
+        let was_present = |s: &Value| old.iter().any(|o| similar(s, o));
+
+        resolved.insert(category.to_string(), old.iter().filter(|s| !still_present(s)).cloned().collect());
+        persisting.insert(category.to_string(), old.iter().filter(|s| still_present(s)).cloned().collect());
+        new.insert(category.to_string(), current.iter().filter(|s| !was_present(s)).cloned().collect());
+    }
+
+    SuggestionChanges {
+        resolved: Value::Object(resolved),
+        new: Value::Object(new),
+        persisting: Value::Object(persisting),
+    }
+}
+
+fn suggestions(value: &Value, category: &str) -> Vec<Value> {
+    value[category]
+        .as_array()
+        .map(|items| items.iter().filter(|s| s.is_string()).cloned().collect())
+        .unwrap_or_default()
+}
+
+fn similar(a: &Value, b: &Value) -> bool {
+    let words = |v: &Value| -> HashSet<String> {
+        v.as_str()
+            .unwrap_or("")
+            .to_lowercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| w.len() > 2)
+            .map(str::to_string)
+            .collect()
+    };
+    let (a, b) = (words(a), words(b));
+    if a.is_empty() || b.is_empty() {
+        return a == b;
+    }
+    let shared = a.intersection(&b).count() as f64;
+    shared / a.union(&b).count() as f64 >= SIMILARITY
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rebuild(chunks: &[DiffChunk], keep: ChangeKind) -> Vec<String> {
+        chunks
+            .iter()
+            .filter(|c| c.kind == ChangeKind::Equal || c.kind == keep)
+            .flat_map(|c| c.lines.clone())
+            .collect()
+    }
+
+    #[test]
+    fn diff_rebuilds_both_texts() {
+        let cases = [
+            ("", ""),
+            ("a\nb\nc", "a\nb\nc"),
+            ("", "a\nb"),
+            ("a\nb", ""),
+            ("a\nb\nc\na\nb\nb\na", "c\nb\na\nb\na\nc"),
+            ("one\ntwo\nthree\nfour", "zero\none\nthree\nfour\nfive"),
+        ];
+        for (before, after) in cases {
+            let chunks = line_diff(before, after).unwrap();
+            assert_eq!(rebuild(&chunks, ChangeKind::Removed), before.lines().collect::<Vec<_>>());
+            assert_eq!(rebuild(&chunks, ChangeKind::Added), after.lines().collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn diff_is_minimal() {
+        let chunks = line_diff("a\nb\nc\na\nb\nb\na", "c\nb\na\nb\na\nc").unwrap();
+        let edits: usize = chunks.iter().filter(|c| c.kind != ChangeKind::Equal).map(|c| c.lines.len()).sum();
+        assert_eq!(edits, 5);
+    }
+
+    #[test]
+    fn too_many_edits_is_a_wholesale_replacement() {
+        let before: String = (0..MAX_EDITS).map(|i| format!("old {}\n", i)).collect();
+        let after: String = (0..MAX_EDITS).map(|i| format!("new {}\n", i)).collect();
+        let chunks = line_diff(&before, &after).unwrap();
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].kind, ChangeKind::Removed);
+        assert_eq!(chunks[1].kind, ChangeKind::Added);
+    }
+
+    #[test]
+    fn long_texts_are_not_diffed() {
+        let long = "line\n".repeat(MAX_LINES + 1);
+        assert!(line_diff(&long, "line").is_none());
+        assert!(line_diff("line", &long).is_none());
+    }
+}
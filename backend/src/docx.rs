@@ -0,0 +1,678 @@
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{Read, Seek};
+
+// Upper bound on any single decompressed part, so a zip bomb can't exhaust
+// memory before we notice.
+const MAX_PART_SIZE: u64 = 32 * 1024 * 1024;
+// Word's own limit on a list's start value.
+const MAX_START: u32 = 32767;
+// Past these, letter and roman markers would be long runs of repeated
+// characters, so the number is written in decimal instead.
+const MAX_LETTERS: u32 = 26 * 30;
+const MAX_ROMAN: u32 = 3999;
+
+#[derive(Debug)]
+pub enum DocxError {
+    // A zip archive, but not a Word document (XLSX, ODT, a plain archive...)
+    NotWordDocument,
+    Empty,
+    Malformed(String),
+}
+
+impl fmt::Display for DocxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DocxError::NotWordDocument => write!(f, "zip archive is not a Word document"),
+            DocxError::Empty => write!(f, "DOCX contains no text"),
+            DocxError::Malformed(e) => write!(f, "DOCX could not be parsed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for DocxError {}
+
+impl From<zip::result::ZipError> for DocxError {
+    fn from(e: zip::result::ZipError) -> Self {
+        DocxError::Malformed(e.to_string())
+    }
+}
+
+impl From<quick_xml::Error> for DocxError {
+    fn from(e: quick_xml::Error) -> Self {
+        DocxError::Malformed(e.to_string())
+    }
+}
+
+// Extracts plain text from a Word (OOXML) document. Headers
+// come first and footers last, each deduplicated since first-page and default
+// headers usually repeat. List paragraphs keep their bullet or number and are
+// indented by level; table rows are read cell by cell.
+pub fn extract_text<R: Read + Seek>(reader: R) -> Result<String, DocxError> {
+    let mut archive = zip::ZipArchive::new(reader)?;
+    if archive.index_for_name("word/document.xml").is_none() {
+        return Err(DocxError::NotWordDocument);
+    }
+
+    let numbering = match read_part(&mut archive, "word/numbering.xml")? {
+        Some(xml) => Numbering::parse(&xml)?,
+        None => Numbering::default(),
+    };
+    let styles = match read_part(&mut archive, "word/styles.xml")? {
+        Some(xml) => parse_style_numbering(&xml)?,
+        None => HashMap::new(),
+    };
+
+    let document = read_part(&mut archive, "word/document.xml")?
+        .ok_or(DocxError::NotWordDocument)?;
+
+    let part_names = |prefix: &str| {
+        let mut names: Vec<String> = archive
+            .file_names()
+            .filter(|n| n.starts_with(prefix) && n.ends_with(".xml"))
+            .map(String::from)
+            .collect();
+        names.sort();
+        names
+    };
+    let headers = part_names("word/header");
+    let footers = part_names("word/footer");
+
+    let mut sections: Vec<String> = Vec::new();
+    let mut walker = Walker::new(&numbering, &styles);
+
+    for name in &headers {
+        if let Some(xml) = read_part(&mut archive, name)? {
+            push_unique(&mut sections, walker.walk(&xml)?);
+        }
+    }
+    sections.push(walker.walk(&document)?);
+    for name in &footers {
+        if let Some(xml) = read_part(&mut archive, name)? {
+            push_unique(&mut sections, walker.walk(&xml)?);
+        }
+    }
+
+    let text = sections
+        .iter()
+        .map(|s| s.trim_matches('\n'))
+        .filter(|s| !s.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    if text.trim().is_empty() {
+        return Err(DocxError::Empty);
+    }
+
+    Ok(text)
+}
+
+fn push_unique(sections: &mut Vec<String>, section: String) {
+    if !section.trim().is_empty() && !sections.contains(&section) {
+        sections.push(section);
+    }
+}
+
+fn read_part<R: Read + Seek>(archive: &mut zip::ZipArchive<R>, name: &str) -> Result<Option<Vec<u8>>, DocxError> {
+    let file = match archive.by_name(name) {
+        Ok(file) => file,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut xml = Vec::new();
+    file.take(MAX_PART_SIZE + 1)
+        .read_to_end(&mut xml)
+        .map_err(|e| DocxError::Malformed(e.to_string()))?;
+    if xml.len() as u64 > MAX_PART_SIZE {
+        return Err(DocxError::Malformed(format!("{} is too large", name)));
+    }
+
+    Ok(Some(xml))
+}
+
+fn attr(e: &BytesStart, name: &str) -> Option<String> {
+    e.try_get_attribute(name)
+        .ok()
+        .flatten()
+        .and_then(|a| a.unescape_value().ok())
+        .map(|v| v.into_owned())
+}
+
+struct Level {
+    format: String,
+    text: String,
+    start: u32,
+}
+
+#[derive(Default)]
+struct Numbering {
+    // abstractNumId -> levels indexed by ilvl
+    abstract_nums: HashMap<String, Vec<Level>>,
+    // numId -> abstractNumId
+    nums: HashMap<String, String>,
+}
+
+impl Numbering {
+    fn parse(xml: &[u8]) -> Result<Self, DocxError> {
+        let mut numbering = Numbering::default();
+        let mut reader = Reader::from_reader(xml);
+        let mut buf = Vec::new();
+
+        let mut abstract_id: Option<String> = None;
+        let mut num_id: Option<String> = None;
+        let mut level: Option<(usize, Level)> = None;
+
+        loop {
+            match reader.read_event_into(&mut buf)? {
+                Event::Start(e) | Event::Empty(e) => match e.name().as_ref() {
+                    b"w:abstractNum" => abstract_id = attr(&e, "w:abstractNumId"),
+                    b"w:num" => num_id = attr(&e, "w:numId"),
+                    b"w:abstractNumId" => {
+                        if let (Some(num), Some(target)) = (&num_id, attr(&e, "w:val")) {
+                            numbering.nums.insert(num.clone(), target);
+                        }
+                    }
+                    b"w:lvl" if abstract_id.is_some() => {
+                        let ilvl = attr(&e, "w:ilvl").and_then(|v| v.parse().ok()).unwrap_or(0usize).min(8);
+                        level = Some((ilvl, Level { format: "decimal".to_string(), text: String::new(), start: 1 }));
+                    }
+                    b"w:start" => {
+                        if let Some((_, lvl)) = level.as_mut() {
+                            lvl.start = attr(&e, "w:val").and_then(|v| v.parse().ok()).unwrap_or(1).min(MAX_START);
+                        }
+                    }
+                    b"w:numFmt" => {
+                        if let (Some((_, lvl)), Some(format)) = (level.as_mut(), attr(&e, "w:val")) {
+                            lvl.format = format;
+                        }
+                    }
+                    b"w:lvlText" => {
+                        if let (Some((_, lvl)), Some(text)) = (level.as_mut(), attr(&e, "w:val")) {
+                            lvl.text = text;
+                        }
+                    }
+                    _ => {}
+                },
+                Event::End(e) => match e.name().as_ref() {
+                    b"w:lvl" => {
+                        if let (Some(id), Some((ilvl, lvl))) = (&abstract_id, level.take()) {
+                            let levels = numbering.abstract_nums.entry(id.clone()).or_default();
+                            while levels.len() <= ilvl {
+                                levels.push(Level { format: "decimal".to_string(), text: String::new(), start: 1 });
+                            }
+                            levels[ilvl] = lvl;
+                        }
+                    }
+                    b"w:abstractNum" => abstract_id = None,
+                    b"w:num" => num_id = None,
+                    _ => {}
+                },
+                Event::Eof => break,
+                _ => {}
+            }
+            buf.clear();
+        }
+
+        Ok(numbering)
+    }
+
+    fn levels(&self, num_id: &str) -> Option<&Vec<Level>> {
+        self.nums.get(num_id).and_then(|id| self.abstract_nums.get(id))
+    }
+}
+
+// Paragraph styles such as "List Bullet" carry their numbering in styles.xml
+// rather than on each paragraph. Returns styleId -> (numId, ilvl).
+fn parse_style_numbering(xml: &[u8]) -> Result<HashMap<String, (String, usize)>, DocxError> {
+    let mut styles = HashMap::new();
+    let mut reader = Reader::from_reader(xml);
+    let mut buf = Vec::new();
+
+    let mut style_id: Option<String> = None;
+    let mut num_id: Option<String> = None;
+    let mut ilvl = 0;
+
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(e) | Event::Empty(e) => match e.name().as_ref() {
+                b"w:style" => {
+                    style_id = attr(&e, "w:styleId");
+                    num_id = None;
+                    ilvl = 0;
+                }
+                b"w:numId" if style_id.is_some() => num_id = attr(&e, "w:val"),
+                b"w:ilvl" if style_id.is_some() => ilvl = attr(&e, "w:val").and_then(|v| v.parse().ok()).unwrap_or(0),
+                _ => {}
+            },
+            Event::End(e) if e.name().as_ref() == b"w:style" => {
+                if let (Some(id), Some(num)) = (style_id.take(), num_id.take()) {
+                    styles.insert(id, (num, ilvl));
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    Ok(styles)
+}
+
+#[derive(Default)]
+struct Paragraph {
+    text: String,
+    style: Option<String>,
+    num_id: Option<String>,
+    ilvl: Option<usize>,
+}
+
+#[derive(Default)]
+struct Table {
+    rows: Vec<Vec<Vec<String>>>,
+    row: Vec<Vec<String>>,
+}
+
+struct Walker<'a> {
+    numbering: &'a Numbering,
+    styles: &'a HashMap<String, (String, usize)>,
+    // numId -> current counter per level; None until the level starts.
+    counters: HashMap<String, [Option<u32>; 9]>,
+}
+
+impl<'a> Walker<'a> {
+    fn new(numbering: &'a Numbering, styles: &'a HashMap<String, (String, usize)>) -> Self {
+        Self { numbering, styles, counters: HashMap::new() }
+    }
+
+    fn walk(&mut self, xml: &[u8]) -> Result<String, DocxError> {
+        let mut reader = Reader::from_reader(xml);
+        let mut buf = Vec::new();
+
+        let mut lines: Vec<String> = Vec::new();
+        let mut paragraphs: Vec<Paragraph> = Vec::new();
+        let mut tables: Vec<Table> = Vec::new();
+        let mut cells: Vec<Vec<String>> = Vec::new();
+
+        let mut in_text = false;
+        let mut in_tab_stops = false;
+        let mut run_depth = 0;
+        let mut property_depth = 0;
+        // Text boxes are written twice, once for DrawingML and once as a VML
+        // fallback; only the first copy is read.
+        let mut fallback_depth = 0;
+
+        loop {
+            let event = reader.read_event_into(&mut buf)?;
+
+            if fallback_depth > 0 {
+                match &event {
+                    Event::Start(e) if e.name().as_ref() == b"mc:Fallback" => fallback_depth += 1,
+                    Event::End(e) if e.name().as_ref() == b"mc:Fallback" => fallback_depth -= 1,
+                    Event::Eof => break,
+                    _ => {}
+                }
+                buf.clear();
+                continue;
+            }
+
+            match event {
+                Event::Start(e) => match e.name().as_ref() {
+                    b"mc:Fallback" => fallback_depth += 1,
+                    b"w:p" => paragraphs.push(Paragraph::default()),
+                    b"w:r" => run_depth += 1,
+                    b"w:t" => in_text = true,
+                    b"w:tabs" => in_tab_stops = true,
+                    b"w:pPrChange" | b"w:rPrChange" => property_depth += 1,
+                    b"w:tbl" => tables.push(Table::default()),
+                    b"w:tc" => cells.push(Vec::new()),
+                    _ => {}
+                },
+                Event::Empty(e) => match e.name().as_ref() {
+                    b"w:p" => sink(&mut cells, &mut lines).push(String::new()),
+                    b"w:pStyle" if property_depth == 0 => {
+                        if let Some(p) = paragraphs.last_mut() {
+                            p.style = attr(&e, "w:val");
+                        }
+                    }
+                    b"w:numId" if property_depth == 0 => {
+                        if let Some(p) = paragraphs.last_mut() {
+                            p.num_id = attr(&e, "w:val");
+                        }
+                    }
+                    b"w:ilvl" if property_depth == 0 => {
+                        if let Some(p) = paragraphs.last_mut() {
+                            p.ilvl = attr(&e, "w:val").and_then(|v| v.parse().ok());
+                        }
+                    }
+                    b"w:tab" if run_depth > 0 && !in_tab_stops => push_text(&mut paragraphs, "\t"),
+                    b"w:br" | b"w:cr" if run_depth > 0 => push_text(&mut paragraphs, "\n"),
+                    b"w:noBreakHyphen" => push_text(&mut paragraphs, "-"),
+                    _ => {}
+                },
+                Event::Text(e) if in_text => {
+                    let text = e.unescape()?;
+                    push_text(&mut paragraphs, &text);
+                }
+                Event::End(e) => match e.name().as_ref() {
+                    b"w:t" => in_text = false,
+                    b"w:tabs" => in_tab_stops = false,
+                    b"w:r" => run_depth -= 1,
+                    b"w:pPrChange" | b"w:rPrChange" => property_depth -= 1,
+                    b"w:p" => {
+                        if let Some(p) = paragraphs.pop() {
+                            let line = self.render_paragraph(p);
+                            sink(&mut cells, &mut lines).push(line);
+                        }
+                    }
+                    b"w:tc" => {
+                        if let (Some(cell), Some(table)) = (cells.pop(), tables.last_mut()) {
+                            table.row.push(cell);
+                        }
+                    }
+                    b"w:tr" => {
+                        if let Some(table) = tables.last_mut() {
+                            let row = std::mem::take(&mut table.row);
+                            table.rows.push(row);
+                        }
+                    }
+                    b"w:tbl" => {
+                        if let Some(table) = tables.pop() {
+                            sink(&mut cells, &mut lines).extend(render_table(table));
+                        }
+                    }
+                    _ => {}
+                },
+                Event::Eof => break,
+                _ => {}
+            }
+            buf.clear();
+        }
+
+        Ok(collapse_blank_lines(&lines))
+    }
+
+    fn render_paragraph(&mut self, p: Paragraph) -> String {
+        let text = p.text.trim_end().to_string();
+        if text.trim().is_empty() {
+            return String::new();
+        }
+
+        let style_numbering = p.style.as_ref().and_then(|s| self.styles.get(s));
+        let num_id = p.num_id.or_else(|| style_numbering.map(|(id, _)| id.clone()));
+        let ilvl = p.ilvl.or_else(|| style_numbering.map(|(_, lvl)| *lvl)).unwrap_or(0).min(8);
+
+        // numId 0 explicitly removes numbering inherited from a style.
+        let levels = match num_id.as_deref() {
+            Some("0") | None => None,
+            Some(id) => self.numbering.levels(id).map(|levels| (id, levels)),
+        };
+
+        let Some((id, levels)) = levels else {
+            return text;
+        };
+
+        let counters = self.counters.entry(id.to_string()).or_insert([None; 9]);
+        let start = levels.get(ilvl).map_or(1, |l| l.start);
+        counters[ilvl] = Some(counters[ilvl].map_or(start, |n| n.saturating_add(1)));
+        for deeper in counters.iter_mut().skip(ilvl + 1) {
+            *deeper = None;
+        }
+
+        let marker = match levels.get(ilvl) {
+            Some(level) => render_marker(level, levels, counters),
+            None => "•".to_string(),
+        };
+
+        let indent = "  ".repeat(ilvl);
+        if marker.is_empty() {
+            format!("{}{}", indent, text.trim_start())
+        } else {
+            format!("{}{} {}", indent, marker, text.trim_start())
+        }
+    }
+}
+
+fn push_text(paragraphs: &mut [Paragraph], text: &str) {
+    if let Some(p) = paragraphs.last_mut() {
+        p.text.push_str(text);
+    }
+}
+
+// Paragraphs inside a table cell belong to that cell, otherwise to the part.
+fn sink<'v>(cells: &'v mut [Vec<String>], lines: &'v mut Vec<String>) -> &'v mut Vec<String> {
+    match cells.last_mut() {
+        Some(cell) => cell,
+        None => lines,
+    }
+}
+
+fn render_marker(level: &Level, levels: &[Level], counters: &[Option<u32>; 9]) -> String {
+    match level.format.as_str() {
+        "bullet" => return "•".to_string(),
+        "none" => return String::new(),
+        _ => {}
+    }
+
+    // lvlText is a template such as "%1." or "%1.%2)" referencing the
+    // counters of this and enclosing levels.
+    let mut marker = level.text.clone();
+    for (i, lvl) in levels.iter().enumerate().take(9).rev() {
+        let placeholder = format!("%{}", i + 1);
+        if marker.contains(&placeholder) {
+            let value = counters[i].unwrap_or(lvl.start);
+            marker = marker.replace(&placeholder, &format_number(value, &lvl.format));
+        }
+    }
+    marker
+}
+
+fn format_number(n: u32, format: &str) -> String {
+    match format {
+        "lowerLetter" if (1..=MAX_LETTERS).contains(&n) => letters(n).to_lowercase(),
+        "upperLetter" if (1..=MAX_LETTERS).contains(&n) => letters(n),
+        "lowerRoman" if (1..=MAX_ROMAN).contains(&n) => roman(n).to_lowercase(),
+        "upperRoman" if (1..=MAX_ROMAN).contains(&n) => roman(n),
+        "decimalZero" => format!("{:02}", n),
+        _ => n.to_string(),
+    }
+}
+
+// Word repeats the letter past Z: A..Z, AA..ZZ, AAA... `n` starts at 1.
+fn letters(n: u32) -> String {
+    let letter = (b'A' + ((n - 1) % 26) as u8) as char;
+    letter.to_string().repeat(((n - 1) / 26 + 1) as usize)
+}
+
+fn roman(mut n: u32) -> String {
+    const NUMERALS: [(u32, &str); 13] = [
+        (1000, "M"), (900, "CM"), (500, "D"), (400, "CD"), (100, "C"), (90, "XC"),
+        (50, "L"), (40, "XL"), (10, "X"), (9, "IX"), (5, "V"), (4, "IV"), (1, "I"),
+    ];
+    let mut out = String::new();
+    for (value, numeral) in NUMERALS {
+        while n >= value {
+            out.push_str(numeral);
+            n -= value;
+        }
+    }
+    out
+}
+
+// Rows whose cells are single short lines read naturally as one line; layout
+// tables (a sidebar cell next to a body cell) are read cell by cell.
+fn render_table(table: Table) -> Vec<String> {
+    let mut lines = Vec::new();
+    for row in table.rows {
+        let cells: Vec<Vec<String>> = row
+            .into_iter()
+            .map(|cell| cell.into_iter().filter(|l| !l.trim().is_empty()).collect::<Vec<_>>())
+            .filter(|cell| !cell.is_empty())
+            .collect();
+
+        if cells.iter().all(|cell| cell.len() == 1) {
+            if !cells.is_empty() {
+                lines.push(cells.iter().map(|cell| cell[0].as_str()).collect::<Vec<_>>().join(" | "));
+            }
+        } else {
+            for cell in cells {
+                lines.extend(cell);
+                lines.push(String::new());
+            }
+        }
+    }
+    lines.push(String::new());
+    lines
+}
+
+fn collapse_blank_lines(lines: &[String]) -> String {
+    let mut out: Vec<&str> = Vec::new();
+    for line in lines {
+        let blank = line.trim().is_empty();
+        if blank && out.last().is_none_or(|l| l.is_empty()) {
+            continue;
+        }
+        out.push(if blank { "" } else { line.as_str() });
+    }
+    while out.last() == Some(&"") {
+        out.pop();
+    }
+    out.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+    use zip::write::SimpleFileOptions;
+
+    const NS: &str = r#"xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main""#;
+
+    fn docx(parts: &[(&str, String)]) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, xml) in parts {
+            zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(xml.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn document(body: &str) -> (&'static str, String) {
+        ("word/document.xml", format!("<w:document {}><w:body>{}</w:body></w:document>", NS, body))
+    }
+
+    fn p(text: &str) -> String {
+        format!("<w:p><w:r><w:t>{}</w:t></w:r></w:p>", text)
+    }
+
+    fn item(text: &str, num_id: u32, ilvl: u32) -> String {
+        format!(
+            r#"<w:p><w:pPr><w:numPr><w:ilvl w:val="{}"/><w:numId w:val="{}"/></w:numPr></w:pPr><w:r><w:t>{}</w:t></w:r></w:p>"#,
+            ilvl, num_id, text
+        )
+    }
+
+    // numId 1 is bullets; numId 2 is "1." with "a)" nested under it, starting
+    // at `start`.
+    fn numbering(start: &str, nested_format: &str) -> (&'static str, String) {
+        let xml = format!(
+            r#"<w:numbering {}>
+            <w:abstractNum w:abstractNumId="0"><w:lvl w:ilvl="0"><w:numFmt w:val="bullet"/><w:lvlText w:val=""/></w:lvl></w:abstractNum>
+            <w:abstractNum w:abstractNumId="1">
+              <w:lvl w:ilvl="0"><w:start w:val="{}"/><w:numFmt w:val="decimal"/><w:lvlText w:val="%1."/></w:lvl>
+              <w:lvl w:ilvl="1"><w:start w:val="1"/><w:numFmt w:val="{}"/><w:lvlText w:val="%2)"/></w:lvl>
+            </w:abstractNum>
+            <w:num w:numId="1"><w:abstractNumId w:val="0"/></w:num>
+            <w:num w:numId="2"><w:abstractNumId w:val="1"/></w:num>
+            </w:numbering>"#,
+            NS, start, nested_format
+        );
+        ("word/numbering.xml", xml)
+    }
+
+    fn extract(parts: &[(&str, String)]) -> Result<String, DocxError> {
+        extract_text(Cursor::new(docx(parts)))
+    }
+
+    #[test]
+    fn keeps_bullets_and_nested_numbers() {
+        let body = [
+            p("EXPERIENCE"),
+            item("Built the billing API", 1, 0),
+            item("Step one", 2, 0),
+            item("Detail", 2, 1),
+            item("More detail", 2, 1),
+            item("Step two", 2, 0),
+            item("Restarted detail", 2, 1),
+        ]
+        .concat();
+        let text = extract(&[document(&body), numbering("1", "lowerLetter")]).unwrap();
+        assert_eq!(
+            text,
+            "EXPERIENCE\n• Built the billing API\n1. Step one\n  a) Detail\n  b) More detail\n2. Step two\n  a) Restarted detail"
+        );
+    }
+
+    #[test]
+    fn lists_can_start_at_zero() {
+        let body = [item("Zero", 2, 0), item("One", 2, 0), item("Two", 2, 0)].concat();
+        let text = extract(&[document(&body), numbering("0", "lowerLetter")]).unwrap();
+        assert_eq!(text, "0. Zero\n1. One\n2. Two");
+    }
+
+    #[test]
+    fn huge_list_numbers_stay_short() {
+        let body = [item("Parent", 2, 0), item("Child", 2, 1)].concat();
+        let text = extract(&[document(&body), numbering("4000000000", "upperLetter")]).unwrap();
+        assert_eq!(text, "32767. Parent\n  A) Child");
+
+        let numbers = numbering("1", "upperRoman").1.replace(
+            r#"<w:start w:val="1"/><w:numFmt w:val="upperRoman"/>"#,
+            r#"<w:start w:val="4294967295"/><w:numFmt w:val="upperRoman"/>"#,
+        );
+        let text = extract(&[document(&body), ("word/numbering.xml", numbers)]).unwrap();
+        assert_eq!(text, "1. Parent\n  32767) Child");
+
+        assert_eq!(format_number(MAX_LETTERS + 1, "upperLetter"), (MAX_LETTERS + 1).to_string());
+        assert_eq!(format_number(MAX_ROMAN + 1, "lowerRoman"), (MAX_ROMAN + 1).to_string());
+        assert_eq!(format_number(0, "upperLetter"), "0");
+        assert_eq!(format_number(28, "upperLetter"), "BB");
+        assert_eq!(format_number(1994, "upperRoman"), "MCMXCIV");
+    }
+
+    #[test]
+    fn reads_tables_row_by_row() {
+        let cell = |paragraphs: &[&str]| format!("<w:tc>{}</w:tc>", paragraphs.iter().map(|t| p(t)).collect::<String>());
+        let table = format!(
+            "<w:tbl><w:tr>{}{}</w:tr><w:tr>{}{}</w:tr></w:tbl>",
+            cell(&["Python"]),
+            cell(&["Expert"]),
+            cell(&["Sidebar A", "Sidebar B"]),
+            cell(&["Body"])
+        );
+        let text = extract(&[document(&format!("{}{}", table, p("After")))]).unwrap();
+        assert_eq!(text, "Python | Expert\nSidebar A\nSidebar B\n\nBody\n\nAfter");
+    }
+
+    #[test]
+    fn repeated_headers_appear_once() {
+        let header = |text: &str| format!("<w:hdr {}>{}</w:hdr>", NS, p(text));
+        let text = extract(&[
+            document(&p("Body")),
+            ("word/header1.xml", header("Jane Doe | jane@example.com")),
+            ("word/header2.xml", header("Jane Doe | jane@example.com")),
+            ("word/footer1.xml", format!("<w:ftr {}>{}</w:ftr>", NS, p("Page"))),
+        ])
+        .unwrap();
+        assert_eq!(text, "Jane Doe | jane@example.com\n\nBody\n\nPage");
+    }
+
+    #[test]
+    fn other_archives_are_not_word_documents() {
+        let result = extract(&[("xl/workbook.xml", "<workbook/>".to_string())]);
+        assert!(matches!(result, Err(DocxError::NotWordDocument)));
+        assert!(matches!(extract(&[document("")]), Err(DocxError::Empty)));
+    }
+}
@@ -4,212 +4,1374 @@ use std::sync::Arc;
 use sqlx::PgPool;
 use futures_util::TryStreamExt;
 use bytes::BufMut;
-use std::fs;
-use std::io::Write;
 use uuid::Uuid;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
 
 use crate::config::Config;
 use crate::models::*;
 use crate::auth::{self, Claims, AuthError};
+use crate::content::{self, ContentError, Extractor};
+use crate::crypto::{self, DataKey, Keyring};
+use crate::diff;
+use crate::jobs::{JobOptions, JobQueue};
+use crate::progress::{self, ProgressHub};
+use crate::storage::{self, PendingObject, Storage};
+use crate::upload::{self, StagedFile, UploadError};
 
+// warp passes each piece of shared state as its own argument
+#[allow(clippy::too_many_arguments)]
 pub async fn upload_resume(
+    claims: Claims,
     form: FormData,
     db_pool: Arc<PgPool>,
-    config: Arc<Config>,
-) -> Result<impl Reply, Rejection> {
-    // Extract user from auth header - for now we'll use a default user
-    let user_id = 1; // TODO: Get from JWT token
+    config: Arc<Config>,
+    job_queue: Arc<JobQueue>,
+    storage: Arc<dyn Storage>,
+    keyring: Arc<Keyring>,
+    extractor: Arc<Extractor>,
+) -> Result<impl Reply, Rejection> {
+    let user_id = claims.sub;
+    // Seals the file as it arrives and the extracted text below
+    let data_key = keyring.new_data_key();
+    
+    let mut form = form;
+    let mut staged: Option<StagedFile> = None;
+    let mut filename = String::new();
+    let mut content_type: Option<String> = None;
+    let mut job_description_id: Option<Uuid> = None;
+    let mut previous_resume_id: Option<Uuid> = None;
+    let mut force = false;
+    
+    // Parts are handled as they arrive; the file goes straight to disk
+    while let Some(part) = form.try_next().await.map_err(|_| warp::reject())? {
+        let name = part.name().to_string();
+        match name.as_str() {
+            "resume" => {
+                filename = storage::display_name(part.filename().unwrap_or_default());
+                content_type = part.content_type().map(|ct| ct.to_string());
+                let sealer = data_key.as_ref().map(DataKey::sealer);
+                staged = match upload::stage(part.stream(), &config.upload_dir, config.max_file_size, sealer).await {
+                    Ok(file) => Some(file),
+                    Err(e @ UploadError::TooLarge { .. }) => {
+                        return Ok(warp::reply::with_status(
+                            warp::reply::json(&serde_json::json!({
+                                "error": e.to_string(),
+                                "code": "file_too_large",
+                            })),
+                            StatusCode::PAYLOAD_TOO_LARGE,
+                        ))
+                    }
+                    Err(e) => {
+                        eprintln!("Upload error: {}", e);
+                        return Err(warp::reject());
+                    }
+                };
+            }
+            "job_description_id" | "previous_resume_id" => {
+                let value = read_part(part).await?;
+                let Some(id) = std::str::from_utf8(&value).ok().and_then(|v| Uuid::parse_str(v.trim()).ok()) else {
+                    return Ok(warp::reply::with_status(
+                        warp::reply::json(&serde_json::json!({"error": format!("{} must be a UUID", name)})),
+                        StatusCode::BAD_REQUEST,
+                    ));
+                };
+                if name == "job_description_id" {
+                    job_description_id = Some(id);
+                } else {
+                    previous_resume_id = Some(id);
+                }
+            }
+            // Bypasses deduplication and the critique cache
+            "force" => {
+                force = match read_part(part).await?.trim_ascii() {
+                    b"true" | b"1" => true,
+                    b"false" | b"0" => false,
+                    _ => {
+                        return Ok(warp::reply::with_status(
+                            warp::reply::json(&serde_json::json!({"error": "force must be true or false"})),
+                            StatusCode::BAD_REQUEST,
+                        ))
+                    }
+                };
+            }
+            _ => {}
+        }
+    }
+    
+    let Some(staged) = staged.filter(|file| file.size() > 0) else {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({"error": "No file uploaded"})),
+            StatusCode::BAD_REQUEST,
+        ));
+    };
+    
+    // A critique can only target one of the user's own job descriptions
+    let job_description = match job_description_id {
+        Some(id) => match find_job_description(&db_pool, id, user_id).await? {
+            Some(jd) => Some(jd.id),
+            None => {
+                return Ok(warp::reply::with_status(
+                    warp::reply::json(&serde_json::json!({"error": "Job description not found"})),
+                    StatusCode::NOT_FOUND,
+                ))
+            }
+        },
+        None => None,
+    };
+    
+    // A new version joins the lineage of one of the user's existing resumes
+    let lineage_id = match previous_resume_id {
+        Some(id) => {
+            let lineage = sqlx::query_scalar!(
+                r#"
+                SELECT fh.lineage_id
+                FROM feedback_history fh
+                JOIN resumes r ON fh.resume_id = r.id
+                WHERE r.public_id = $1 AND r.user_id = $2
+                "#,
+                id,
+                user_id
+            )
+            .fetch_optional(&*db_pool)
+            .await
+            .map_err(|e| {
+                eprintln!("Database error: {}", e);
+                warp::reject()
+            })?;
+            
+            match lineage {
+                Some(lineage_id) => Some(lineage_id),
+                None => {
+                    return Ok(warp::reply::with_status(
+                        warp::reply::json(&serde_json::json!({"error": "Previous resume not found"})),
+                        StatusCode::NOT_FOUND,
+                    ))
+                }
+            }
+        }
+        None => None,
+    };
+    
+    // The same file again: reuse the stored resume instead of writing a
+    // second copy of it
+    let file_hash = staged.hash().to_string();
+    let existing = if force {
+        None
+    } else {
+        find_duplicate(&db_pool, user_id, &file_hash, lineage_id).await.map_err(|e| {
+            eprintln!("Database error: {}", e);
+            warp::reject()
+        })?
+    };
+    
+    let duplicate = existing.is_some();
+    let (mut tx, resume_id, resume_public_id, version_number, text_hash, stored) = match existing {
+        Some(existing) => {
+            let tx = db_pool.begin().await.map_err(|e| {
+                eprintln!("Database error: {}", e);
+                warp::reject()
+            })?;
+            (tx, existing.id, existing.public_id, existing.version_number, existing.text_hash, None)
+        }
+        None => {
+            // Validate against the actual bytes and extract text before anything is
+            // persisted, so rejected files never create rows or cost an AI call
+            let head = staged.head(data_key.as_ref()).await.map_err(|e| {
+                eprintln!("Upload error: {}", e);
+                warp::reject()
+            })?;
+            let kind = match content::validate(content_type.as_deref(), &head) {
+                Ok(kind) => kind,
+                Err(e) => return Ok(content_error_reply(&e)),
+            };
+            let extracted = extractor
+                .extract_text(kind, staged.path().to_path_buf(), data_key.as_ref().map(|key| key.opener()))
+                .await
+                .map_err(|e| {
+                    eprintln!("Upload error: {}", e);
+                    warp::reject()
+                })?;
+            let content = match extracted {
+                Ok(text) => text,
+                Err(e) => return Ok(content_error_reply(&e)),
+            };
+            let text_hash = content::text_hash(&content);
+            let content_type = kind.mime_type().to_string();
+            
+            // Store the file; it is removed again if the transaction below
+            // doesn't commit
+            let file_size = staged.size() as i32;
+            let stored = PendingObject::put(storage, storage::object_key(Uuid::new_v4()), staged)
+                .await
+                .map_err(|e| {
+                    eprintln!("Storage error: {}", e);
+                    warp::reject()
+                })?;
+            
+            // Save the resume and queue its critique in one transaction, so a job
+            // never points at a missing resume and a resume never lacks a job
+            let mut tx = db_pool.begin().await.map_err(|e| {
+                eprintln!("Database error: {}", e);
+                warp::reject()
+            })?;
+            
+            let resume = sqlx::query_as!(
+                Resume,
+                r#"
+                INSERT INTO resumes (
+                    user_id, filename, original_content, storage_key, file_size, mime_type,
+                    file_hash, text_hash, data_key, data_key_id
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                RETURNING id, public_id, user_id AS "user_id!", filename, original_content, storage_key, file_size, mime_type, uploaded_at
+                "#,
+                user_id,
+                filename,
+                match &data_key {
+                    Some(key) => key.seal_text(&content),
+                    None => content,
+                },
+                Some(stored.key()),
+                Some(file_size),
+                Some(content_type),
+                file_hash,
+                text_hash,
+                data_key.as_ref().map(DataKey::wrapped),
+                data_key.as_ref().map(DataKey::key_id)
+            )
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| {
+                eprintln!("Database error: {}", e);
+                warp::reject()
+            })?;
+            
+            let version = record_version(&mut tx, user_id, resume.id, lineage_id).await.map_err(|e| {
+                eprintln!("Database error: {}", e);
+                warp::reject()
+            })?;
+            
+            (tx, resume.id, resume.public_id, version.version_number, Some(text_hash), Some(stored))
+        }
+    };
+    
+    // Content already scored under the same rubric is answered from the
+    // cache instead of another AI call
+    let cached = match text_hash.as_deref().filter(|_| !force) {
+        Some(text_hash) => cached_critique(&mut tx, &config, resume_id, user_id, text_hash, job_description)
+            .await
+            .map_err(|e| {
+                eprintln!("Database error: {}", e);
+                warp::reject()
+            })?,
+        None => None,
+    };
+    
+    // A duplicate of a resume whose critique is still in progress shares
+    // that job rather than paying for the same critique twice
+    let pending = if duplicate && cached.is_none() {
+        sqlx::query_scalar!(
+            r#"
+            SELECT public_id FROM critique_jobs
+            WHERE resume_id = $1
+              AND status IN ('queued', 'running')
+              AND job_description_id IS NOT DISTINCT FROM $2
+              AND model IS NULL
+              AND rubric IS NULL
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#,
+            resume_id,
+            job_description
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| {
+            eprintln!("Database error: {}", e);
+            warp::reject()
+        })?
+    } else {
+        None
+    };
+    
+    let job_id = match (cached, pending) {
+        (Some(_), _) => None,
+        (None, Some(pending)) => Some(pending),
+        (None, None) => Some(
+            job_queue
+                .enqueue(
+                    &mut tx,
+                    resume_id,
+                    JobOptions {
+                        job_description_id: job_description,
+                        ..Default::default()
+                    },
+                )
+                .await
+                .map_err(|e| {
+                    eprintln!("Database error: {}", e);
+                    warp::reject()
+                })?,
+        ),
+    };
+    
+    tx.commit().await.map_err(|e| {
+        eprintln!("Database error: {}", e);
+        warp::reject()
+    })?;
+    
+    if let Some(stored) = stored {
+        stored.keep();
+    }
+    
+    let message = match (duplicate, cached.is_some()) {
+        (false, false) => "Resume uploaded; critique queued",
+        (false, true) => "Resume uploaded; returning the critique of identical content",
+        (true, false) => "Resume already uploaded; critique queued",
+        (true, true) => "Resume already uploaded; returning its critique",
+    };
+    
+    let status = if job_id.is_some() {
+        job_queue.wake();
+        StatusCode::ACCEPTED
+    } else {
+        StatusCode::OK
+    };
+    
+    let response = UploadResponse {
+        message: message.to_string(),
+        resume_id: resume_public_id,
+        version_number,
+        job_id,
+        critique_id: cached,
+        duplicate,
+        cached: cached.is_some(),
+    };
+    
+    Ok(warp::reply::with_status(
+        warp::reply::json(&response),
+        status,
+    ))
+}
+
+struct Duplicate {
+    id: i32,
+    public_id: Uuid,
+    text_hash: Option<String>,
+    version_number: i32,
+}
+
+// The user's latest resume with exactly these bytes. When the upload names a
+// lineage only a resume already in it counts; the same file sent as a new
+// version of another resume is stored again so that lineage gets its version.
+async fn find_duplicate(
+    db_pool: &PgPool,
+    user_id: i32,
+    file_hash: &str,
+    lineage_id: Option<Uuid>,
+) -> Result<Option<Duplicate>, sqlx::Error> {
+    sqlx::query_as!(
+        Duplicate,
+        r#"
+        SELECT r.id, r.public_id, r.text_hash, fh.version_number
+        FROM resumes r
+        JOIN feedback_history fh ON fh.resume_id = r.id
+        WHERE r.user_id = $1 AND r.file_hash = $2
+          AND ($3::uuid IS NULL OR fh.lineage_id = $3)
+        ORDER BY r.uploaded_at DESC, r.id DESC
+        LIMIT 1
+        "#,
+        user_id,
+        file_hash,
+        lineage_id
+    )
+    .fetch_optional(db_pool)
+    .await
+}
+
+// Finds a critique of the same text that the current scoring setup would
+// reproduce: same RUBRIC_VERSION, same job description, default options, and
+// written by the configured scorer (a fallback critique from an outage
+// doesn't count). One belonging to another resume is copied to this one.
+async fn cached_critique(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    config: &Config,
+    resume_id: i32,
+    user_id: i32,
+    text_hash: &str,
+    job_description_id: Option<i32>,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    let cached = sqlx::query!(
+        r#"
+        SELECT c.id, c.public_id, c.resume_id AS "resume_id!"
+        FROM critiques c
+        JOIN resumes r ON c.resume_id = r.id
+        WHERE r.user_id = $1
+          AND r.text_hash = $2
+          AND c.rubric_version = $3
+          AND c.generated_by = $4
+          AND c.job_description_id IS NOT DISTINCT FROM $5
+          AND c.model IS NULL
+          AND c.rubric IS NULL
+        ORDER BY c.resume_id = $6 DESC, c.created_at DESC
+        LIMIT 1
+        "#,
+        user_id,
+        text_hash,
+        config.rubric_version,
+        config.scorer,
+        job_description_id,
+        resume_id
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    
+    let Some(cached) = cached else {
+        return Ok(None);
+    };
+    if cached.resume_id == resume_id {
+        return Ok(Some(cached.public_id));
+    }
+    
+    let copy = sqlx::query!(
+        r#"
+        INSERT INTO critiques (
+            resume_id, overall_score, structure_score, keywords_score,
+            action_verbs_score, quantified_impact_score, readability_score,
+            detailed_feedback, improvement_suggestions, generated_by, validation_issues,
+            job_description_id, fit_score, keyword_report, model, rubric, rubric_version
+        )
+        SELECT $1, overall_score, structure_score, keywords_score,
+               action_verbs_score, quantified_impact_score, readability_score,
+               detailed_feedback, improvement_suggestions, generated_by, validation_issues,
+               job_description_id, fit_score, keyword_report, model, rubric, rubric_version
+        FROM critiques
+        WHERE id = $2
+        RETURNING id, public_id
+        "#,
+        resume_id,
+        cached.id
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+    
+    sqlx::query!(
+        "UPDATE feedback_history SET critique_id = $2 WHERE resume_id = $1",
+        resume_id,
+        copy.id
+    )
+    .execute(&mut **tx)
+    .await?;
+    
+    Ok(Some(copy.public_id))
+}
+
+pub async fn create_job_description(
+    claims: Claims,
+    request: CreateJobDescriptionRequest,
+    db_pool: Arc<PgPool>,
+) -> Result<impl Reply, Rejection> {
+    let title = request.title.trim();
+    let description = request.description.trim();
+    let company = request.company.as_deref().map(str::trim).filter(|c| !c.is_empty());
+    
+    if title.is_empty() || description.is_empty() {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({"error": "Title and description are required"})),
+            StatusCode::BAD_REQUEST,
+        ));
+    }
+    
+    let job_description = sqlx::query_as!(
+        JobDescription,
+        r#"
+        INSERT INTO job_descriptions (user_id, title, company, description)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, public_id, user_id, title, company, description, created_at
+        "#,
+        claims.sub,
+        title,
+        company,
+        description
+    )
+    .fetch_one(&*db_pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Database error: {}", e);
+        warp::reject()
+    })?;
+    
+    Ok(warp::reply::with_status(
+        warp::reply::json(&JobDescriptionResponse::from(job_description)),
+        StatusCode::CREATED,
+    ))
+}
+
+pub async fn list_job_descriptions(
+    claims: Claims,
+    db_pool: Arc<PgPool>,
+) -> Result<impl Reply, Rejection> {
+    let job_descriptions = sqlx::query_as!(
+        JobDescription,
+        "SELECT * FROM job_descriptions WHERE user_id = $1 ORDER BY created_at DESC",
+        claims.sub
+    )
+    .fetch_all(&*db_pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Database error: {}", e);
+        warp::reject()
+    })?;
+    
+    let response = JobDescriptionListResponse {
+        job_descriptions: job_descriptions.into_iter().map(JobDescriptionResponse::from).collect(),
+    };
+    
+    Ok(warp::reply::with_status(
+        warp::reply::json(&response),
+        StatusCode::OK,
+    ))
+}
+
+pub async fn get_job_description(
+    job_description_id: Uuid,
+    claims: Claims,
+    db_pool: Arc<PgPool>,
+) -> Result<impl Reply, Rejection> {
+    match find_job_description(&db_pool, job_description_id, claims.sub).await? {
+        Some(jd) => Ok(warp::reply::with_status(
+            warp::reply::json(&JobDescriptionResponse::from(jd)),
+            StatusCode::OK,
+        )),
+        None => Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({"error": "Job description not found"})),
+            StatusCode::NOT_FOUND,
+        )),
+    }
+}
+
+// Job descriptions are private to the user who saved them.
+async fn find_job_description(
+    db_pool: &PgPool,
+    job_description_id: Uuid,
+    user_id: i32,
+) -> Result<Option<JobDescription>, Rejection> {
+    sqlx::query_as!(
+        JobDescription,
+        "SELECT * FROM job_descriptions WHERE public_id = $1 AND user_id = $2",
+        job_description_id,
+        user_id
+    )
+    .fetch_optional(db_pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Database error: {}", e);
+        warp::reject()
+    })
+}
+
+pub async fn get_job(
+    job_id: Uuid,
+    claims: Claims,
+    db_pool: Arc<PgPool>,
+) -> Result<impl Reply, Rejection> {
+    let job = sqlx::query!(
+        r#"
+        SELECT j.public_id, j.status, j.attempts, j.error, j.error_code,
+               j.created_at, j.started_at, j.finished_at,
+               c.public_id AS "critique_public_id?"
+        FROM critique_jobs j
+        JOIN resumes r ON j.resume_id = r.id
+        LEFT JOIN critiques c ON j.critique_id = c.id
+        WHERE j.public_id = $1 AND (r.user_id = $2 OR $3)
+        "#,
+        job_id,
+        claims.sub,
+        claims.is_admin()
+    )
+    .fetch_optional(&*db_pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Database error: {}", e);
+        warp::reject()
+    })?;
+    
+    match job {
+        Some(j) => {
+            let response = JobResponse {
+                id: j.public_id,
+                status: j.status,
+                attempts: j.attempts,
+                critique_id: j.critique_public_id,
+                error: j.error,
+                error_code: j.error_code,
+                created_at: j.created_at,
+                started_at: j.started_at,
+                finished_at: j.finished_at,
+            };
+            
+            Ok(warp::reply::with_status(
+                warp::reply::json(&response),
+                StatusCode::OK,
+            ))
+        }
+        None => Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({"error": "Job not found"})),
+            StatusCode::NOT_FOUND,
+        )),
+    }
+}
+
+pub async fn get_job_events(
+    job_id: Uuid,
+    claims: Claims,
+    db_pool: Arc<PgPool>,
+    progress_hub: Arc<ProgressHub>,
+) -> Result<warp::reply::Response, Rejection> {
+    // Subscribe first so nothing published while the snapshot is read is lost
+    let receiver = progress_hub.subscribe();
+    
+    let job = sqlx::query!(
+        r#"
+        SELECT j.public_id, j.status, j.stage, j.error, j.error_code,
+               c.public_id AS "critique_public_id?"
+        FROM critique_jobs j
+        JOIN resumes r ON j.resume_id = r.id
+        LEFT JOIN critiques c ON j.critique_id = c.id
+        WHERE j.public_id = $1 AND (r.user_id = $2 OR $3)
+        "#,
+        job_id,
+        claims.sub,
+        claims.is_admin()
+    )
+    .fetch_optional(&*db_pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Database error: {}", e);
+        warp::reject()
+    })?;
+    
+    match job {
+        Some(j) => {
+            let snapshot = progress::Snapshot {
+                job_id: j.public_id,
+                status: j.status,
+                stage: j.stage,
+                critique_id: j.critique_public_id,
+                error: j.error,
+                error_code: j.error_code,
+            };
+            let events = progress::event_stream(db_pool, snapshot, receiver);
+            Ok(warp::sse::reply(warp::sse::keep_alive().stream(events)).into_response())
+        }
+        None => Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({"error": "Job not found"})),
+            StatusCode::NOT_FOUND,
+        )
+        .into_response()),
+    }
+}
+
+pub async fn get_critique(
+    critique_id: Uuid,
+    claims: Claims,
+    db_pool: Arc<PgPool>,
+    _config: Arc<Config>,
+) -> Result<impl Reply, Rejection> {
+    match fetch_critique(&db_pool, critique_id, &claims).await? {
+        Some(response) => Ok(warp::reply::with_status(
+            warp::reply::json(&response),
+            StatusCode::OK,
+        )),
+        None => Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({"error": "Critique not found"})),
+            StatusCode::NOT_FOUND,
+        )),
+    }
+}
+
+// Other users' critiques are indistinguishable from missing ones
+async fn fetch_critique(
+    db_pool: &PgPool,
+    critique_id: Uuid,
+    claims: &Claims,
+) -> Result<Option<CritiqueResponse>, Rejection> {
+    let critique = sqlx::query!(
+        r#"
+        SELECT c.*, r.filename, jd.public_id AS "job_description_public_id?"
+        FROM critiques c
+        JOIN resumes r ON c.resume_id = r.id
+        LEFT JOIN job_descriptions jd ON c.job_description_id = jd.id
+        WHERE c.public_id = $1 AND (r.user_id = $2 OR $3)
+        "#,
+        critique_id,
+        claims.sub,
+        claims.is_admin()
+    )
+    .fetch_optional(db_pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Database error: {}", e);
+        warp::reject()
+    })?;
+    
+    Ok(critique.map(|c| CritiqueResponse {
+        id: c.public_id,
+        resume_filename: c.filename,
+        overall_score: c.overall_score,
+        scores: CritiqueScores {
+            structure: c.structure_score,
+            keywords: c.keywords_score,
+            action_verbs: c.action_verbs_score,
+            quantified_impact: c.quantified_impact_score,
+            readability: c.readability_score,
+        },
+        detailed_feedback: c.detailed_feedback,
+        improvement_suggestions: c.improvement_suggestions,
+        generated_by: c.generated_by,
+        validation_issues: c.validation_issues,
+        job_description_id: c.job_description_public_id,
+        fit_score: c.fit_score,
+        keyword_report: c.keyword_report,
+        model: c.model,
+        rubric: c.rubric,
+        created_at: c.created_at,
+    }))
+}
+
+// Compares two of the caller's critiques, typically of successive versions of
+// a resume. Only the owner may diff; admins have no use for it.
+pub async fn diff_critiques(
+    first_id: Uuid,
+    second_id: Uuid,
+    claims: Claims,
+    db_pool: Arc<PgPool>,
+    keyring: Arc<Keyring>,
+) -> Result<impl Reply, Rejection> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT c.public_id, c.overall_score, c.structure_score, c.keywords_score,
+               c.action_verbs_score, c.quantified_impact_score, c.readability_score,
+               c.improvement_suggestions, c.created_at, r.filename, r.original_content,
+               r.data_key, r.data_key_id
+        FROM critiques c
+        JOIN resumes r ON c.resume_id = r.id
+        WHERE c.public_id = ANY($1) AND r.user_id = $2
+        ORDER BY c.created_at, c.id
+        "#,
+        &[first_id, second_id][..],
+        claims.sub
+    )
+    .fetch_all(&*db_pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Database error: {}", e);
+        warp::reject()
+    })?;
+
+    let expected = if first_id == second_id { 1 } else { 2 };
+    if rows.len() != expected {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({"error": "Critique not found"})),
+            StatusCode::NOT_FOUND,
+        ));
+    }
+
+    let (before, after) = (&rows[0], &rows[rows.len() - 1]);
+    let open = |content: &String, key_id: &Option<String>, key: &Option<Vec<u8>>| {
+        keyring.open_text(key_id.as_deref(), key.as_deref(), content.clone()).map_err(|e| {
+            eprintln!("Decryption error: {}", e);
+            warp::reject()
+        })
+    };
+    let before_text = open(&before.original_content, &before.data_key_id, &before.data_key)?;
+    let after_text = open(&after.original_content, &after.data_key_id, &after.data_key)?;
+    let response = CritiqueDiffResponse {
+        from_critique_id: before.public_id,
+        to_critique_id: after.public_id,
+        from_filename: before.filename.clone(),
+        to_filename: after.filename.clone(),
+        scores: ScoreDeltas {
+            overall: ScoreDelta::new(before.overall_score, after.overall_score),
+            structure: ScoreDelta::new(before.structure_score, after.structure_score),
+            keywords: ScoreDelta::new(before.keywords_score, after.keywords_score),
+            action_verbs: ScoreDelta::new(before.action_verbs_score, after.action_verbs_score),
+            quantified_impact: ScoreDelta::new(before.quantified_impact_score, after.quantified_impact_score),
+            readability: ScoreDelta::new(before.readability_score, after.readability_score),
+        },
+        suggestions: diff::compare_suggestions(&before.improvement_suggestions, &after.improvement_suggestions),
+        content_diff: diff::line_diff(&before_text, &after_text),
+    };
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&response),
+        StatusCode::OK,
+    ))
+}
+
+pub async fn list_resumes(
+    claims: Claims,
+    db_pool: Arc<PgPool>,
+) -> Result<impl Reply, Rejection> {
+    let resumes = fetch_resumes(&db_pool, claims.sub, None).await?;
+    
+    Ok(warp::reply::with_status(
+        warp::reply::json(&ResumeListResponse { resumes }),
+        StatusCode::OK,
+    ))
+}
+
+pub async fn get_resume(
+    resume_id: Uuid,
+    claims: Claims,
+    db_pool: Arc<PgPool>,
+    keyring: Arc<Keyring>,
+) -> Result<impl Reply, Rejection> {
+    let Some(resume) = fetch_resumes(&db_pool, claims.sub, Some(resume_id)).await?.pop() else {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({"error": "Resume not found"})),
+            StatusCode::NOT_FOUND,
+        ));
+    };
+    
+    let stored = sqlx::query!(
+        "SELECT original_content, data_key, data_key_id FROM resumes WHERE public_id = $1",
+        resume_id
+    )
+    .fetch_one(&*db_pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Database error: {}", e);
+        warp::reject()
+    })?;
+    let content = keyring
+        .open_text(stored.data_key_id.as_deref(), stored.data_key.as_deref(), stored.original_content)
+        .map_err(|e| {
+            eprintln!("Decryption error: {}", e);
+            warp::reject()
+        })?;
+    
+    let critiques = sqlx::query_as!(
+        ResumeCritiqueSummary,
+        r#"
+        SELECT c.public_id AS id, c.overall_score, c.generated_by, c.created_at
+        FROM critiques c
+        JOIN resumes r ON c.resume_id = r.id
+        WHERE r.public_id = $1
+        ORDER BY c.created_at DESC, c.id DESC
+        "#,
+        resume_id
+    )
+    .fetch_all(&*db_pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Database error: {}", e);
+        warp::reject()
+    })?;
+    
+    let response = ResumeDetailResponse {
+        resume,
+        content,
+        critiques,
+    };
+    
+    Ok(warp::reply::with_status(
+        warp::reply::json(&response),
+        StatusCode::OK,
+    ))
+}
+
+// The caller's resumes, newest first, or just the one with `resume_id`
+async fn fetch_resumes(
+    db_pool: &PgPool,
+    user_id: i32,
+    resume_id: Option<Uuid>,
+) -> Result<Vec<ResumeResponse>, Rejection> {
+    sqlx::query_as!(
+        ResumeResponse,
+        r#"
+        SELECT r.public_id AS id, r.filename, r.file_size, r.mime_type, fh.lineage_id,
+               fh.version_number,
+               (SELECT COUNT(*) FROM critiques c WHERE c.resume_id = r.id) AS "critique_count!",
+               latest.public_id AS "latest_critique_id?",
+               latest.overall_score AS "latest_overall_score?",
+               r.uploaded_at
+        FROM resumes r
+        JOIN feedback_history fh ON fh.resume_id = r.id
+        LEFT JOIN LATERAL (
+            SELECT c.public_id, c.overall_score
+            FROM critiques c
+            WHERE c.resume_id = r.id
+            ORDER BY c.created_at DESC, c.id DESC
+            LIMIT 1
+        ) latest ON TRUE
+        WHERE r.user_id = $1 AND ($2::uuid IS NULL OR r.public_id = $2)
+        ORDER BY r.uploaded_at DESC, r.id DESC
+        "#,
+        user_id,
+        resume_id
+    )
+    .fetch_all(db_pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Database error: {}", e);
+        warp::reject()
+    })
+}
+
+// Streams the file as uploaded rather than reading it into memory
+pub async fn download_resume(
+    resume_id: Uuid,
+    claims: Claims,
+    db_pool: Arc<PgPool>,
+    storage: Arc<dyn Storage>,
+    keyring: Arc<Keyring>,
+) -> Result<warp::reply::Response, Rejection> {
+    let resume = sqlx::query!(
+        r#"
+        SELECT filename, storage_key, mime_type, data_key, data_key_id
+        FROM resumes WHERE public_id = $1 AND user_id = $2
+        "#,
+        resume_id,
+        claims.sub
+    )
+    .fetch_optional(&*db_pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Database error: {}", e);
+        warp::reject()
+    })?;
+    
+    let Some(resume) = resume else {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({"error": "Resume not found"})),
+            StatusCode::NOT_FOUND,
+        )
+        .into_response());
+    };
+    
+    let blob = match &resume.storage_key {
+        Some(key) => storage.get(key).await,
+        None => Err(std::io::ErrorKind::NotFound.into()),
+    };
+    let blob = match blob {
+        Ok(blob) => blob,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({"error": "Original file is no longer available"})),
+                StatusCode::NOT_FOUND,
+            )
+            .into_response());
+        }
+        Err(e) => {
+            eprintln!("Failed to open resume file: {}", e);
+            return Err(warp::reject());
+        }
+    };
+    
+    // Sealed files are opened as they stream
+    let (size, body) = match (resume.data_key_id.as_deref(), resume.data_key.as_deref()) {
+        (Some(key_id), Some(wrapped)) => {
+            let key = keyring.data_key(key_id, wrapped).map_err(|e| {
+                eprintln!("Decryption error: {}", e);
+                warp::reject()
+            })?;
+            (blob.size.and_then(crypto::plaintext_len), crypto::decrypt_stream(key.opener(), blob.stream))
+        }
+        _ => (blob.size, blob.stream),
+    };
+    
+    let mut response = warp::http::Response::builder()
+        .header(
+            warp::http::header::CONTENT_TYPE,
+            resume.mime_type.as_deref().unwrap_or("application/octet-stream"),
+        )
+        .header(warp::http::header::CONTENT_DISPOSITION, attachment_disposition(&resume.filename))
+        .header("X-Content-Type-Options", "nosniff");
+    if let Some(size) = size {
+        response = response.header(warp::http::header::CONTENT_LENGTH, size);
+    }
+    response
+        .body(warp::hyper::Body::wrap_stream(body))
+        .map_err(|_| warp::reject())
+}
+
+// Quoted ASCII name for old clients plus the exact UTF-8 name (RFC 6266)
+fn attachment_disposition(filename: &str) -> String {
+    let fallback: String = filename
+        .chars()
+        .map(|c| if (c.is_ascii_graphic() || c == ' ') && c != '"' && c != '\\' { c } else { '_' })
+        .collect();
+    let encoded: String = filename
+        .bytes()
+        .map(|b| {
+            if b.is_ascii_alphanumeric() || b"-._~".contains(&b) {
+                (b as char).to_string()
+            } else {
+                format!("%{:02X}", b)
+            }
+        })
+        .collect();
+    format!("attachment; filename=\"{}\"; filename*=UTF-8''{}", fallback, encoded)
+}
+
+// Deleting a resume removes its critiques, jobs and version entry with it
+// (ON DELETE CASCADE), then the stored file.
+pub async fn delete_resume(
+    resume_id: Uuid,
+    claims: Claims,
+    db_pool: Arc<PgPool>,
+    storage: Arc<dyn Storage>,
+) -> Result<warp::reply::Response, Rejection> {
+    let deleted = sqlx::query_scalar!(
+        "DELETE FROM resumes WHERE public_id = $1 AND user_id = $2 RETURNING storage_key",
+        resume_id,
+        claims.sub
+    )
+    .fetch_optional(&*db_pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Database error: {}", e);
+        warp::reject()
+    })?;
     
-    let parts: Vec<_> = form.try_collect().await.map_err(|_| warp::reject())?;
+    let Some(storage_key) = deleted else {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({"error": "Resume not found"})),
+            StatusCode::NOT_FOUND,
+        )
+        .into_response());
+    };
     
-    let mut file_data = Vec::new();
-    let mut filename = String::new();
-    let mut content_type = String::new();
+    // The rows are already gone, so a file that can't be removed is only
+    // logged; it is unreachable through the API either way.
+    if let Some(key) = storage_key {
+        if let Err(e) = storage.delete(&key).await {
+            eprintln!("Failed to remove resume file {}: {}", key, e);
+        }
+    }
     
-    for part in parts {
-        if part.name() == "resume" {
-            filename = part.filename().unwrap_or("resume.pdf").to_string();
-            content_type = part.content_type().unwrap_or("application/pdf").to_string();
-            
-            let stream = part.stream();
-            let data: Vec<u8> = stream
-                .try_fold(Vec::new(), |mut vec, data| {
-                    vec.put(data);
-                    async move { Ok(vec) }
-                })
-                .await
-                .map_err(|_| warp::reject())?;
-            
-            file_data = data;
-            break;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+const MAX_RUBRIC_CHARS: usize = 2000;
+
+// Critiques a stored resume again from its extracted text, adding a critique
+// alongside the earlier ones instead of requiring a re-upload.
+pub async fn create_resume_critique(
+    resume_id: Uuid,
+    claims: Claims,
+    request: CreateCritiqueRequest,
+    db_pool: Arc<PgPool>,
+    config: Arc<Config>,
+    job_queue: Arc<JobQueue>,
+) -> Result<impl Reply, Rejection> {
+    let model = request.model.as_deref().map(str::trim).filter(|m| !m.is_empty());
+    if let Some(model) = model {
+        if !config.ai_models.iter().any(|m| m == model) {
+            let error = if config.ai_models.is_empty() {
+                "Model selection is not enabled".to_string()
+            } else {
+                format!("model must be one of: {}", config.ai_models.join(", "))
+            };
+            return Ok(warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({"error": error})),
+                StatusCode::BAD_REQUEST,
+            ));
         }
     }
     
-    if file_data.is_empty() {
+    let rubric = request.rubric.as_deref().map(str::trim).filter(|r| !r.is_empty());
+    if rubric.is_some_and(|r| r.chars().count() > MAX_RUBRIC_CHARS) {
         return Ok(warp::reply::with_status(
-            warp::reply::json(&serde_json::json!({"error": "No file uploaded"})),
+            warp::reply::json(&serde_json::json!({
+                "error": format!("rubric must be at most {} characters", MAX_RUBRIC_CHARS)
+            })),
             StatusCode::BAD_REQUEST,
         ));
     }
     
-    // Create uploads directory if it doesn't exist
-    fs::create_dir_all(&config.upload_dir).map_err(|_| warp::reject())?;
-    
-    // Save file to disk
-    let file_id = Uuid::new_v4().to_string();
-    let file_path = format!("{}/{}-{}", config.upload_dir, file_id, filename);
-    let mut file = fs::File::create(&file_path).map_err(|_| warp::reject())?;
-    file.write_all(&file_data).map_err(|_| warp::reject())?;
-    
-    // Extract text content (simplified - in production you'd use proper PDF parsing)
-    let content = match content_type.as_str() {
-        "application/pdf" => extract_pdf_text(&file_path).await.unwrap_or_else(|_| "PDF content extraction failed".to_string()),
-        "text/plain" => String::from_utf8_lossy(&file_data).to_string(),
-        _ => "Unsupported file type".to_string(),
-    };
-    
-    // Save resume to database
-    let resume = sqlx::query_as!(
-        Resume,
-        r#"
-        INSERT INTO resumes (user_id, filename, original_content, file_path, file_size, mime_type)
-        VALUES ($1, $2, $3, $4, $5, $6)
-        RETURNING id, user_id, filename, original_content, file_path, file_size, mime_type, uploaded_at
-        "#,
-        user_id,
-        filename,
-        content,
-        Some(file_path),
-        Some(file_data.len() as i32),
-        Some(content_type)
+    let resume = sqlx::query_scalar!(
+        "SELECT id FROM resumes WHERE public_id = $1 AND user_id = $2",
+        resume_id,
+        claims.sub
     )
-    .fetch_one(&**db_pool)
+    .fetch_optional(&*db_pool)
     .await
     .map_err(|e| {
         eprintln!("Database error: {}", e);
         warp::reject()
     })?;
     
-    // Call AI service for critique
-    let ai_request = AiCritiqueRequest {
-        resume_text: content,
-        filename: filename.clone(),
+    let Some(resume) = resume else {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({"error": "Resume not found"})),
+            StatusCode::NOT_FOUND,
+        ));
+    };
+    
+    let job_description = match request.job_description_id {
+        Some(id) => match find_job_description(&db_pool, id, claims.sub).await? {
+            Some(jd) => Some(jd.id),
+            None => {
+                return Ok(warp::reply::with_status(
+                    warp::reply::json(&serde_json::json!({"error": "Job description not found"})),
+                    StatusCode::NOT_FOUND,
+                ))
+            }
+        },
+        None => None,
     };
     
-    let client = reqwest::Client::new();
-    let ai_response = client
-        .post(&format!("{}/critique", config.ai_service_url))
-        .json(&ai_request)
-        .send()
+    let mut tx = db_pool.begin().await.map_err(|e| {
+        eprintln!("Database error: {}", e);
+        warp::reject()
+    })?;
+    
+    let job_id = job_queue
+        .enqueue(
+            &mut tx,
+            resume,
+            JobOptions {
+                job_description_id: job_description,
+                model: model.map(str::to_string),
+                rubric: rubric.map(str::to_string),
+            },
+        )
         .await
         .map_err(|e| {
-            eprintln!("AI service error: {}", e);
+            eprintln!("Database error: {}", e);
             warp::reject()
         })?;
     
-    if !ai_response.status().is_success() {
-        return Ok(warp::reply::with_status(
-            warp::reply::json(&serde_json::json!({"error": "AI service failed"})),
-            StatusCode::INTERNAL_SERVER_ERROR,
-        ));
-    }
+    tx.commit().await.map_err(|e| {
+        eprintln!("Database error: {}", e);
+        warp::reject()
+    })?;
+    job_queue.wake();
     
-    let ai_critique: AiCritiqueResponse = ai_response
-        .json()
-        .await
-        .map_err(|_| warp::reject())?;
+    let response = CritiqueQueuedResponse {
+        message: "Critique queued".to_string(),
+        resume_id,
+        job_id,
+    };
+    
+    Ok(warp::reply::with_status(
+        warp::reply::json(&response),
+        StatusCode::ACCEPTED,
+    ))
+}
+
+pub async fn list_versions(
+    resume_id: Uuid,
+    claims: Claims,
+    db_pool: Arc<PgPool>,
+) -> Result<impl Reply, Rejection> {
+    let versions = fetch_versions(&db_pool, resume_id, &claims).await?;
+    
+    match versions {
+        Some(response) => Ok(warp::reply::with_status(
+            warp::reply::json(&response),
+            StatusCode::OK,
+        )),
+        None => Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({"error": "Resume not found"})),
+            StatusCode::NOT_FOUND,
+        )),
+    }
+}
+
+pub async fn update_version_notes(
+    resume_id: Uuid,
+    version_number: i32,
+    claims: Claims,
+    request: VersionNotesRequest,
+    db_pool: Arc<PgPool>,
+) -> Result<impl Reply, Rejection> {
+    let notes = request.notes.as_deref().map(str::trim).filter(|n| !n.is_empty());
     
-    // Save critique to database
-    let critique = sqlx::query_as!(
-        Critique,
+    // Only the owner may annotate their versions
+    let updated = sqlx::query!(
         r#"
-        INSERT INTO critiques (
-            resume_id, overall_score, structure_score, keywords_score, 
-            action_verbs_score, quantified_impact_score, readability_score,
-            detailed_feedback, improvement_suggestions
+        UPDATE feedback_history SET notes = $4
+        WHERE version_number = $3 AND lineage_id = (
+            SELECT fh.lineage_id FROM feedback_history fh
+            JOIN resumes r ON fh.resume_id = r.id
+            WHERE r.public_id = $1 AND r.user_id = $2
         )
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
-        RETURNING id, resume_id, overall_score, structure_score, keywords_score,
-                  action_verbs_score, quantified_impact_score, readability_score,
-                  detailed_feedback, improvement_suggestions, created_at
         "#,
-        resume.id,
-        ai_critique.overall_score,
-        ai_critique.structure_score,
-        ai_critique.keywords_score,
-        ai_critique.action_verbs_score,
-        ai_critique.quantified_impact_score,
-        ai_critique.readability_score,
-        ai_critique.detailed_feedback,
-        ai_critique.improvement_suggestions
+        resume_id,
+        claims.sub,
+        version_number,
+        notes
     )
-    .fetch_one(&**db_pool)
+    .execute(&*db_pool)
     .await
     .map_err(|e| {
         eprintln!("Database error: {}", e);
         warp::reject()
     })?;
     
-    let response = UploadResponse {
-        message: "Resume uploaded and analyzed successfully".to_string(),
-        critique_id: critique.id,
+    let version = match updated.rows_affected() {
+        0 => None,
+        _ => fetch_versions(&db_pool, resume_id, &claims)
+            .await?
+            .and_then(|list| list.versions.into_iter().find(|v| v.version_number == version_number)),
     };
     
-    Ok(warp::reply::with_status(
-        warp::reply::json(&response),
-        StatusCode::OK,
-    ))
+    match version {
+        Some(version) => Ok(warp::reply::with_status(
+            warp::reply::json(&version),
+            StatusCode::OK,
+        )),
+        None => Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({"error": "Version not found"})),
+            StatusCode::NOT_FOUND,
+        )),
+    }
 }
 
-pub async fn get_critique(
-    critique_id: i32,
+pub async fn get_version_critique(
+    resume_id: Uuid,
+    version_number: i32,
+    claims: Claims,
     db_pool: Arc<PgPool>,
-    _config: Arc<Config>,
 ) -> Result<impl Reply, Rejection> {
-    let critique = sqlx::query!(
+    let version = fetch_versions(&db_pool, resume_id, &claims)
+        .await?
+        .and_then(|list| list.versions.into_iter().find(|v| v.version_number == version_number));
+    
+    let Some(version) = version else {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({"error": "Version not found"})),
+            StatusCode::NOT_FOUND,
+        ));
+    };
+    
+    let critique = match version.critique_id {
+        Some(critique_id) => fetch_critique(&db_pool, critique_id, &claims).await?,
+        None => None,
+    };
+    
+    match critique {
+        Some(response) => Ok(warp::reply::with_status(
+            warp::reply::json(&response),
+            StatusCode::OK,
+        )),
+        None => Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({"error": "This version has no critique yet"})),
+            StatusCode::NOT_FOUND,
+        )),
+    }
+}
+
+// All versions in the lineage of the given resume, oldest first; None if
+// the resume doesn't exist or isn't visible to the caller.
+async fn fetch_versions(
+    db_pool: &PgPool,
+    resume_id: Uuid,
+    claims: &Claims,
+) -> Result<Option<VersionListResponse>, Rejection> {
+    let lineage = sqlx::query!(
         r#"
-        SELECT c.*, r.filename 
-        FROM critiques c
-        JOIN resumes r ON c.resume_id = r.id
-        WHERE c.id = $1
+        SELECT fh.lineage_id
+        FROM feedback_history fh
+        JOIN resumes r ON fh.resume_id = r.id
+        WHERE r.public_id = $1 AND (r.user_id = $2 OR $3)
         "#,
-        critique_id
+        resume_id,
+        claims.sub,
+        claims.is_admin()
     )
-    .fetch_optional(&**db_pool)
+    .fetch_optional(db_pool)
     .await
     .map_err(|e| {
         eprintln!("Database error: {}", e);
         warp::reject()
     })?;
     
-    match critique {
-        Some(c) => {
-            let response = CritiqueResponse {
-                id: c.id,
-                resume_filename: c.filename,
-                overall_score: c.overall_score,
-                scores: CritiqueScores {
-                    structure: c.structure_score,
-                    keywords: c.keywords_score,
-                    action_verbs: c.action_verbs_score,
-                    quantified_impact: c.quantified_impact_score,
-                    readability: c.readability_score,
-                },
-                detailed_feedback: c.detailed_feedback,
-                improvement_suggestions: c.improvement_suggestions,
-                created_at: c.created_at,
-            };
-            
-            Ok(warp::reply::with_status(
-                warp::reply::json(&response),
-                StatusCode::OK,
-            ))
+    let Some(lineage) = lineage else {
+        return Ok(None);
+    };
+    
+    let versions = sqlx::query!(
+        r#"
+        SELECT fh.version_number, fh.notes, fh.created_at,
+               r.public_id AS resume_public_id, r.filename,
+               c.public_id AS "critique_public_id?", c.overall_score AS "overall_score?"
+        FROM feedback_history fh
+        JOIN resumes r ON fh.resume_id = r.id
+        LEFT JOIN critiques c ON fh.critique_id = c.id
+        WHERE fh.lineage_id = $1
+        ORDER BY fh.version_number
+        "#,
+        lineage.lineage_id
+    )
+    .fetch_all(db_pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Database error: {}", e);
+        warp::reject()
+    })?;
+    
+    Ok(Some(VersionListResponse {
+        lineage_id: lineage.lineage_id,
+        versions: versions
+            .into_iter()
+            .map(|v| ResumeVersionResponse {
+                version_number: v.version_number,
+                resume_id: v.resume_public_id,
+                filename: v.filename,
+                notes: v.notes,
+                critique_id: v.critique_public_id,
+                overall_score: v.overall_score,
+                created_at: v.created_at,
+            })
+            .collect(),
+    }))
+}
+
+// Adds the resume to an existing lineage as its next version, or starts a
+// new lineage at version 1.
+async fn record_version(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: i32,
+    resume_id: i32,
+    lineage_id: Option<Uuid>,
+) -> Result<FeedbackHistory, sqlx::Error> {
+    let (lineage_id, version_number) = match lineage_id {
+        Some(lineage_id) => {
+            // Locking the lineage serialises concurrent uploads to it, so each
+            // gets a distinct version number.
+            sqlx::query!("SELECT id FROM feedback_history WHERE lineage_id = $1 FOR UPDATE", lineage_id)
+                .fetch_all(&mut **tx)
+                .await?;
+            let next = sqlx::query_scalar!(
+                "SELECT COALESCE(MAX(version_number), 0) + 1 FROM feedback_history WHERE lineage_id = $1",
+                lineage_id
+            )
+            .fetch_one(&mut **tx)
+            .await?;
+            (lineage_id, next.unwrap_or(1))
         }
-        None => Ok(warp::reply::with_status(
-            warp::reply::json(&serde_json::json!({"error": "Critique not found"})),
-            StatusCode::NOT_FOUND,
-        )),
-    }
+        None => (Uuid::new_v4(), 1),
+    };
+    
+    sqlx::query_as!(
+        FeedbackHistory,
+        r#"
+        INSERT INTO feedback_history (user_id, resume_id, lineage_id, version_number)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, user_id, resume_id, critique_id, lineage_id, version_number, notes, created_at
+        "#,
+        user_id,
+        resume_id,
+        lineage_id,
+        version_number
+    )
+    .fetch_one(&mut **tx)
+    .await
 }
 
 pub async fn login(
@@ -222,7 +1384,7 @@ pub async fn login(
         "SELECT * FROM users WHERE email = $1",
         request.email
     )
-    .fetch_optional(&**db_pool)
+    .fetch_optional(&*db_pool)
     .await
     .map_err(|e| {
         eprintln!("Database error: {}", e);
@@ -232,7 +1394,7 @@ pub async fn login(
     match user {
         Some(u) => {
             if auth::verify_password(&request.password, &u.password_hash).unwrap_or(false) {
-                let token = auth::create_jwt(u.id, &u.email, &config.jwt_secret)
+                let token = auth::create_jwt(u.id, &u.email, &u.role, &config.jwt_secret)
                     .map_err(|_| warp::reject())?;
                 
                 let response = AuthResponse {
@@ -275,20 +1437,20 @@ pub async fn register(
         r#"
         INSERT INTO users (email, password_hash, name)
         VALUES ($1, $2, $3)
-        RETURNING id, email, password_hash, name, created_at, updated_at
+        RETURNING id, email, password_hash, name, role, created_at, updated_at
         "#,
         request.email,
         password_hash,
         request.name
     )
-    .fetch_one(&**db_pool)
+    .fetch_one(&*db_pool)
     .await
     .map_err(|e| {
         eprintln!("Database error: {}", e);
         warp::reject()
     })?;
     
-    let token = auth::create_jwt(user.id, &user.email, &config.jwt_secret)
+    let token = auth::create_jwt(user.id, &user.email, &user.role, &config.jwt_secret)
         .map_err(|_| warp::reject())?;
     
     let response = AuthResponse {
@@ -319,31 +1481,109 @@ pub async fn get_current_user(claims: Claims) -> Result<impl Reply, Rejection> {
     ))
 }
 
+const DEFAULT_HISTORY_LIMIT: i64 = 20;
+const MAX_HISTORY_LIMIT: i64 = 100;
+
+// A page of the user's critiques. Paging is keyset-based: each page
+// continues strictly after the last entry of the previous one, so uploads
+// made meanwhile don't shift or repeat entries.
 pub async fn get_history(
     claims: Claims,
+    query: HistoryQuery,
     db_pool: Arc<PgPool>,
 ) -> Result<impl Reply, Rejection> {
-    let critiques = sqlx::query!(
+    let limit = query.limit.unwrap_or(DEFAULT_HISTORY_LIMIT).clamp(1, MAX_HISTORY_LIMIT);
+    
+    let cursor = match query.cursor.as_deref().map(decode_history_cursor) {
+        None => None,
+        Some(Some(c)) if c.sort == query.sort && c.order == query.order => Some(c),
+        Some(_) => {
+            return Ok(warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({"error": "Invalid cursor"})),
+                StatusCode::BAD_REQUEST,
+            ))
+        }
+    };
+    
+    // Both orderings share one query: the sort key is the score or the
+    // timestamp, negated for descending order, with the id as tie-breaker.
+    let direction = if query.order == SortOrder::Desc { -1.0 } else { 1.0 };
+    let filename = query
+        .filename
+        .as_deref()
+        .map(str::trim)
+        .filter(|f| !f.is_empty())
+        .map(|f| format!("%{}%", f.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")));
+    
+    let rows = sqlx::query!(
         r#"
-        SELECT c.*, r.filename 
+        SELECT c.public_id, r.public_id AS resume_public_id, r.filename, c.overall_score,
+               c.structure_score, c.keywords_score, c.action_verbs_score,
+               c.quantified_impact_score, c.readability_score, c.generated_by, c.fit_score,
+               c.created_at, jd.public_id AS "job_description_public_id?",
+               CASE WHEN $12 THEN c.detailed_feedback END AS detailed_feedback,
+               CASE WHEN $12 THEN c.improvement_suggestions END AS improvement_suggestions,
+               CASE WHEN $12 THEN c.validation_issues END AS validation_issues,
+               CASE WHEN $12 THEN c.keyword_report END AS keyword_report,
+               k.sort_key AS "sort_key!"
         FROM critiques c
         JOIN resumes r ON c.resume_id = r.id
+        LEFT JOIN job_descriptions jd ON c.job_description_id = jd.id
+        CROSS JOIN LATERAL (
+            SELECT $9::float8 * CASE WHEN $8 THEN c.overall_score::float8
+                                     ELSE EXTRACT(EPOCH FROM c.created_at)::float8 END AS sort_key
+        ) k
         WHERE r.user_id = $1
-        ORDER BY c.created_at DESC
+          AND ($2::timestamptz IS NULL OR c.created_at >= $2)
+          AND ($3::timestamptz IS NULL OR c.created_at <= $3)
+          AND ($4::real IS NULL OR c.overall_score >= $4)
+          AND ($5::real IS NULL OR c.overall_score <= $5)
+          AND ($6::text IS NULL OR r.filename ILIKE $6)
+          AND ($7::uuid IS NULL OR r.public_id = $7)
+          AND ($10::float8 IS NULL OR (k.sort_key, c.public_id) > ($10, $11::uuid))
+        ORDER BY k.sort_key, c.public_id
+        LIMIT $13
         "#,
-        claims.sub
+        claims.sub,
+        query.from,
+        query.to,
+        query.min_score,
+        query.max_score,
+        filename,
+        query.resume_id,
+        query.sort == HistorySort::OverallScore,
+        direction,
+        cursor.as_ref().map(|c| c.key),
+        cursor.as_ref().map(|c| c.id),
+        query.include_feedback,
+        limit + 1
     )
-    .fetch_all(&**db_pool)
+    .fetch_all(&*db_pool)
     .await
     .map_err(|e| {
         eprintln!("Database error: {}", e);
         warp::reject()
     })?;
     
-    let critique_responses: Vec<CritiqueResponse> = critiques
+    // One row past the page tells whether there is another page
+    let next_cursor = if rows.len() as i64 > limit {
+        let last = &rows[limit as usize - 1];
+        Some(encode_history_cursor(&HistoryCursor {
+            sort: query.sort,
+            order: query.order,
+            key: last.sort_key,
+            id: last.public_id,
+        }))
+    } else {
+        None
+    };
+    
+    let critiques: Vec<HistoryEntry> = rows
         .into_iter()
-        .map(|c| CritiqueResponse {
-            id: c.id,
+        .take(limit as usize)
+        .map(|c| HistoryEntry {
+            id: c.public_id,
+            resume_id: c.resume_public_id,
             resume_filename: c.filename,
             overall_score: c.overall_score,
             scores: CritiqueScores {
@@ -353,14 +1593,168 @@ pub async fn get_history(
                 quantified_impact: c.quantified_impact_score,
                 readability: c.readability_score,
             },
+            generated_by: c.generated_by,
+            job_description_id: c.job_description_public_id,
+            fit_score: c.fit_score,
+            created_at: c.created_at,
             detailed_feedback: c.detailed_feedback,
             improvement_suggestions: c.improvement_suggestions,
-            created_at: c.created_at,
+            validation_issues: c.validation_issues,
+            keyword_report: c.keyword_report,
         })
         .collect();
     
     let response = HistoryResponse {
-        critiques: critique_responses,
+        critiques,
+        next_cursor,
+    };
+    
+    Ok(warp::reply::with_status(
+        warp::reply::json(&response),
+        StatusCode::OK,
+    ))
+}
+
+fn encode_history_cursor(cursor: &HistoryCursor) -> String {
+    let json = serde_json::to_vec(cursor).expect("cursor serializes");
+    URL_SAFE_NO_PAD.encode(json)
+}
+
+fn decode_history_cursor(cursor: &str) -> Option<HistoryCursor> {
+    let json = URL_SAFE_NO_PAD.decode(cursor).ok()?;
+    serde_json::from_slice(&json).ok()
+}
+
+const DEFAULT_ANALYTICS_WINDOW: i64 = 3;
+const MAX_ANALYTICS_WINDOW: i64 = 50;
+
+// Score trends across all of the user's critiques. The aggregation happens in
+// Postgres so only the per-critique scores and five summary rows come back,
+// however many uploads the user has.
+pub async fn get_analytics(
+    claims: Claims,
+    query: AnalyticsQuery,
+    db_pool: Arc<PgPool>,
+) -> Result<impl Reply, Rejection> {
+    let window = query.window.unwrap_or(DEFAULT_ANALYTICS_WINDOW).clamp(1, MAX_ANALYTICS_WINDOW);
+    
+    let points = sqlx::query!(
+        r#"
+        SELECT c.public_id, c.created_at, c.overall_score, c.structure_score, c.keywords_score,
+               c.action_verbs_score, c.quantified_impact_score, c.readability_score,
+               ROUND((AVG(c.overall_score) OVER w)::numeric, 2)::REAL AS "overall_average!",
+               ROUND((AVG(c.structure_score) OVER w)::numeric, 2)::REAL AS "structure_average!",
+               ROUND((AVG(c.keywords_score) OVER w)::numeric, 2)::REAL AS "keywords_average!",
+               ROUND((AVG(c.action_verbs_score) OVER w)::numeric, 2)::REAL AS "action_verbs_average!",
+               ROUND((AVG(c.quantified_impact_score) OVER w)::numeric, 2)::REAL AS "quantified_impact_average!",
+               ROUND((AVG(c.readability_score) OVER w)::numeric, 2)::REAL AS "readability_average!"
+        FROM critiques c
+        JOIN resumes r ON c.resume_id = r.id
+        WHERE r.user_id = $1
+        WINDOW w AS (ORDER BY c.created_at, c.id ROWS BETWEEN $2 PRECEDING AND CURRENT ROW)
+        ORDER BY c.created_at, c.id
+        "#,
+        claims.sub,
+        window - 1
+    )
+    .fetch_all(&*db_pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Database error: {}", e);
+        warp::reject()
+    })?;
+    
+    // One row per dimension, best average first
+    let dimensions = sqlx::query!(
+        r#"
+        WITH scored AS (
+            SELECT c.*, ROW_NUMBER() OVER (ORDER BY c.created_at, c.id) AS seq
+            FROM critiques c
+            JOIN resumes r ON c.resume_id = r.id
+            WHERE r.user_id = $1
+        )
+        SELECT d.dimension AS "dimension!",
+               ROUND(AVG(d.score)::numeric, 2)::REAL AS "average!",
+               MIN(d.score)::REAL AS "min!",
+               MAX(d.score)::REAL AS "max!",
+               (ARRAY_AGG(d.score ORDER BY s.seq))[1]::REAL AS "first!",
+               (ARRAY_AGG(d.score ORDER BY s.seq DESC))[1]::REAL AS "latest!",
+               ROUND(REGR_SLOPE(d.score, s.seq::float8)::numeric, 3)::REAL AS velocity_per_critique,
+               CASE WHEN MAX(s.created_at) - MIN(s.created_at) >= INTERVAL '7 days' THEN
+                   ROUND((REGR_SLOPE(d.score, EXTRACT(EPOCH FROM s.created_at)::float8) * 2592000)::numeric, 3)::REAL
+               END AS velocity_per_30_days
+        FROM scored s
+        CROSS JOIN LATERAL (VALUES
+            ('overall', s.overall_score),
+            ('structure', s.structure_score),
+            ('keywords', s.keywords_score),
+            ('action_verbs', s.action_verbs_score),
+            ('quantified_impact', s.quantified_impact_score),
+            ('readability', s.readability_score)
+        ) AS d(dimension, score)
+        GROUP BY d.dimension
+        ORDER BY AVG(d.score) DESC, d.dimension
+        "#,
+        claims.sub
+    )
+    .fetch_all(&*db_pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Database error: {}", e);
+        warp::reject()
+    })?;
+    
+    let dimensions: Vec<DimensionTrend> = dimensions
+        .into_iter()
+        .map(|d| DimensionTrend {
+            dimension: d.dimension,
+            average: d.average,
+            min: d.min,
+            max: d.max,
+            first: d.first,
+            latest: d.latest,
+            velocity_per_critique: d.velocity_per_critique,
+            velocity_per_30_days: d.velocity_per_30_days,
+        })
+        .collect();
+    
+    let categories: Vec<&DimensionTrend> = dimensions.iter().filter(|d| d.dimension != "overall").collect();
+    let best_dimension = categories.first().map(|d| d.dimension.clone());
+    let worst_dimension = categories.last().map(|d| d.dimension.clone());
+    
+    let series: Vec<AnalyticsPoint> = points
+        .into_iter()
+        .map(|p| AnalyticsPoint {
+            critique_id: p.public_id,
+            created_at: p.created_at,
+            overall_score: p.overall_score,
+            scores: CritiqueScores {
+                structure: p.structure_score,
+                keywords: p.keywords_score,
+                action_verbs: p.action_verbs_score,
+                quantified_impact: p.quantified_impact_score,
+                readability: p.readability_score,
+            },
+            moving_average: MovingAverage {
+                overall_score: p.overall_average,
+                scores: CritiqueScores {
+                    structure: p.structure_average,
+                    keywords: p.keywords_average,
+                    action_verbs: p.action_verbs_average,
+                    quantified_impact: p.quantified_impact_average,
+                    readability: p.readability_average,
+                },
+            },
+        })
+        .collect();
+    
+    let response = AnalyticsResponse {
+        critique_count: series.len(),
+        window,
+        best_dimension,
+        worst_dimension,
+        dimensions,
+        series,
     };
     
     Ok(warp::reply::with_status(
@@ -379,6 +1773,12 @@ pub async fn handle_rejection(err: Rejection) -> Result<impl Reply, std::convert
     } else if err.find::<AuthError>().is_some() {
         code = StatusCode::UNAUTHORIZED;
         message = "Unauthorized";
+    } else if err.find::<warp::reject::InvalidQuery>().is_some() {
+        code = StatusCode::BAD_REQUEST;
+        message = "Invalid query string";
+    } else if err.find::<warp::reject::PayloadTooLarge>().is_some() {
+        code = StatusCode::PAYLOAD_TOO_LARGE;
+        message = "Request body too large";
     } else if err.find::<warp::reject::MethodNotAllowed>().is_some() {
         code = StatusCode::METHOD_NOT_ALLOWED;
         message = "Method Not Allowed";
@@ -395,9 +1795,75 @@ pub async fn handle_rejection(err: Rejection) -> Result<impl Reply, std::convert
     Ok(warp::reply::with_status(json, code))
 }
 
-// Helper function for PDF text extraction (simplified)
-async fn extract_pdf_text(file_path: &str) -> Result<String, Box<dyn std::error::Error>> {
-    // In a real implementation, you'd use a proper PDF library
-    // For now, return a placeholder
-    Ok(format!("PDF content from file: {}", file_path))
+async fn read_part(part: warp::multipart::Part) -> Result<Vec<u8>, Rejection> {
+    part.stream()
+        .try_fold(Vec::new(), |mut vec, data| {
+            vec.put(data);
+            async move { Ok(vec) }
+        })
+        .await
+        .map_err(|_| warp::reject())
+}
+
+pub(crate) fn content_error_reply(err: &ContentError) -> warp::reply::WithStatus<warp::reply::Json> {
+    warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({
+            "error": err.to_string(),
+            "code": err.code(),
+        })),
+        err.status(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_db;
+
+    const HASH: &str = "4bf5122f344554c53bde2ebb8cd2b7e3d1600ad631c385a5d7cce23c7785459a";
+
+    // Sets the file hash of `resume_id` and returns its lineage.
+    async fn upload(db_pool: &PgPool, resume_id: i32) -> Uuid {
+        sqlx::query_scalar!(
+            r#"
+            WITH r AS (UPDATE resumes SET file_hash = $2 WHERE id = $1)
+            SELECT lineage_id FROM feedback_history WHERE resume_id = $1
+            "#,
+            resume_id,
+            HASH
+        )
+        .fetch_one(db_pool)
+        .await
+        .unwrap()
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn duplicates_stay_in_the_requested_lineage(db_pool: PgPool) {
+        test_db::schema(&db_pool).await;
+        let resume_id = test_db::resume(&db_pool, "Jane Doe\nBackend engineer").await;
+        let lineage_id = upload(&db_pool, resume_id).await;
+        let user_id = sqlx::query_scalar!("SELECT user_id AS \"user_id!\" FROM resumes WHERE id = $1", resume_id)
+            .fetch_one(&db_pool)
+            .await
+            .unwrap();
+
+        // A second lineage the same file could be sent as a new version of
+        let mut tx = db_pool.begin().await.unwrap();
+        let other_id = sqlx::query_scalar!(
+            "INSERT INTO resumes (user_id, filename, original_content) VALUES ($1, 'other.txt', 'Other') RETURNING id",
+            user_id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .unwrap();
+        let other = record_version(&mut tx, user_id, other_id, None).await.unwrap();
+        tx.commit().await.unwrap();
+
+        let found = find_duplicate(&db_pool, user_id, HASH, None).await.unwrap().expect("no lineage: any copy counts");
+        assert_eq!(found.id, resume_id);
+        let found = find_duplicate(&db_pool, user_id, HASH, Some(lineage_id)).await.unwrap().expect("same lineage");
+        assert_eq!((found.id, found.version_number), (resume_id, 1));
+        assert!(find_duplicate(&db_pool, user_id, HASH, Some(other.lineage_id)).await.unwrap().is_none());
+        assert!(find_duplicate(&db_pool, user_id + 1, HASH, None).await.unwrap().is_none());
+    }
 }
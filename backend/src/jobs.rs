@@ -0,0 +1,458 @@
+use sqlx::{PgPool, Postgres, Transaction};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::crypto::{CryptoError, Keyring};
+use crate::matching;
+use crate::models::*;
+use crate::progress::{self, ProgressEvent, Stage};
+use crate::scorer::{ScoreError, Scorer};
+
+// How long a worker owns a claimed job. It must comfortably exceed the
+// slowest AI round-trip; after it lapses another worker may take the job over.
+const LEASE_SECS: f64 = 300.0;
+// Fallback poll for jobs enqueued by other instances or released by backoff.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+const RETRY_BASE_SECS: f64 = 10.0;
+
+// What a job should be critiqued against and how. Unset fields use the
+// scorer's defaults.
+#[derive(Debug, Default)]
+pub struct JobOptions {
+    pub job_description_id: Option<i32>,
+    pub model: Option<String>,
+    pub rubric: Option<String>,
+}
+
+// Critique jobs are stored in Postgres so they outlive the process; the
+// in-memory signal only lets idle workers pick up new work immediately.
+pub struct JobQueue {
+    notify: Notify,
+}
+
+impl JobQueue {
+    pub fn new() -> Self {
+        Self { notify: Notify::new() }
+    }
+
+    pub async fn enqueue(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        resume_id: i32,
+        options: JobOptions,
+    ) -> Result<Uuid, sqlx::Error> {
+        let job = sqlx::query!(
+            r#"
+            INSERT INTO critique_jobs (resume_id, job_description_id, model, rubric)
+            VALUES ($1, $2, $3, $4)
+            RETURNING public_id
+            "#,
+            resume_id,
+            options.job_description_id,
+            options.model,
+            options.rubric
+        )
+        .fetch_one(&mut **tx)
+        .await?;
+
+        Ok(job.public_id)
+    }
+
+    // Call once the enqueuing transaction has committed.
+    pub fn wake(&self) {
+        self.notify.notify_one();
+    }
+}
+
+struct ClaimedJob {
+    id: i32,
+    public_id: Uuid,
+    resume_id: i32,
+    // Identifies this claim: a worker that takes over the job after the
+    // lease lapses claims it with a higher count.
+    attempts: i32,
+}
+
+// Why a job run failed. `code` is stored with the job so clients can branch
+// on it without parsing the message.
+struct JobFailure {
+    code: &'static str,
+    message: String,
+    // Set when the job never reached the AI service and should wait this
+    // long without using up an attempt.
+    retry_after: Option<Duration>,
+}
+
+impl From<ScoreError> for JobFailure {
+    fn from(e: ScoreError) -> Self {
+        Self {
+            code: e.code,
+            message: e.message,
+            retry_after: e.retry_after,
+        }
+    }
+}
+
+impl From<CryptoError> for JobFailure {
+    fn from(e: CryptoError) -> Self {
+        Self {
+            code: "decryption_failed",
+            message: e.to_string(),
+            retry_after: None,
+        }
+    }
+}
+
+impl From<sqlx::Error> for JobFailure {
+    fn from(e: sqlx::Error) -> Self {
+        Self {
+            code: "database_error",
+            message: format!("Database error: {}", e),
+            retry_after: None,
+        }
+    }
+}
+
+pub fn spawn_workers(
+    db_pool: Arc<PgPool>,
+    config: Arc<Config>,
+    queue: Arc<JobQueue>,
+    scorer: Arc<dyn Scorer>,
+    keyring: Arc<Keyring>,
+) {
+    for _ in 0..config.job_workers {
+        let db_pool = db_pool.clone();
+        let config = config.clone();
+        let queue = queue.clone();
+        let scorer = scorer.clone();
+        let keyring = keyring.clone();
+        tokio::spawn(async move { worker_loop(db_pool, config, queue, scorer, keyring).await });
+    }
+}
+
+async fn worker_loop(
+    db_pool: Arc<PgPool>,
+    config: Arc<Config>,
+    queue: Arc<JobQueue>,
+    scorer: Arc<dyn Scorer>,
+    keyring: Arc<Keyring>,
+) {
+    loop {
+        match claim_next(&db_pool).await {
+            Ok(Some(job)) => run_job(&db_pool, &config, &*scorer, &keyring, job).await,
+            Ok(None) => {
+                tokio::select! {
+                    _ = queue.notify.notified() => {}
+                    _ = tokio::time::sleep(POLL_INTERVAL) => {}
+                }
+            }
+            Err(e) => {
+                eprintln!("Job queue error: {}", e);
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        }
+    }
+}
+
+// Takes the oldest runnable job, including running jobs whose worker died
+// without finishing (expired lease). SKIP LOCKED lets several workers and
+// several backend instances poll the same table without contention.
+async fn claim_next(db_pool: &PgPool) -> Result<Option<ClaimedJob>, sqlx::Error> {
+    sqlx::query_as!(
+        ClaimedJob,
+        r#"
+        UPDATE critique_jobs
+        SET status = 'running',
+            attempts = attempts + 1,
+            started_at = NOW(),
+            locked_until = NOW() + make_interval(secs => $1)
+        WHERE id = (
+            SELECT id FROM critique_jobs
+            WHERE (status = 'queued' AND available_at <= NOW())
+               OR (status = 'running' AND locked_until < NOW())
+            ORDER BY available_at
+            FOR UPDATE SKIP LOCKED
+            LIMIT 1
+        )
+        RETURNING id, public_id, resume_id, attempts
+        "#,
+        LEASE_SECS
+    )
+    .fetch_optional(db_pool)
+    .await
+}
+
+async fn run_job(db_pool: &PgPool, config: &Config, scorer: &dyn Scorer, keyring: &Keyring, job: ClaimedJob) {
+    let result = if job.attempts > config.job_max_attempts {
+        Err(JobFailure {
+            code: "retry_limit_exceeded",
+            message: "Job exceeded its retry limit".to_string(),
+            retry_after: None,
+        })
+    } else {
+        process(db_pool, config, scorer, keyring, &job).await
+    };
+
+    if let Err(failure) = result {
+        eprintln!("Critique job {} failed (attempt {}): {}", job.id, job.attempts, failure.message);
+        match release_failed(db_pool, config, &job, &failure).await {
+            Ok(true) => {}
+            Ok(false) => {
+                eprintln!("Critique job {} was taken over by another worker; dropping its failure", job.id);
+                return;
+            }
+            Err(e) => eprintln!("Database error: {}", e),
+        }
+
+        let will_retry = failure.retry_after.is_some() || job.attempts < config.job_max_attempts;
+        let event = ProgressEvent::new(job.public_id, Stage::Failed).with_data(serde_json::json!({
+            "error": failure.message,
+            "code": failure.code,
+            "will_retry": will_retry,
+        }));
+        report(db_pool, &job, event).await;
+    }
+}
+
+// Progress is best effort; a failed notification must not fail the job.
+async fn report(db_pool: &PgPool, job: &ClaimedJob, event: ProgressEvent) {
+    if let Err(e) = progress::publish(db_pool, job.id, &event).await {
+        eprintln!("Progress notification error: {}", e);
+    }
+}
+
+async fn process(
+    db_pool: &PgPool,
+    config: &Config,
+    scorer: &dyn Scorer,
+    keyring: &Keyring,
+    job: &ClaimedJob,
+) -> Result<(), JobFailure> {
+    let resume = sqlx::query!(
+        r#"
+        SELECT r.filename, r.original_content, r.data_key, r.data_key_id, j.model, j.rubric,
+               jd.id AS "job_description_id?", jd.description AS "job_description?"
+        FROM critique_jobs j
+        JOIN resumes r ON j.resume_id = r.id
+        LEFT JOIN job_descriptions jd ON j.job_description_id = jd.id
+        WHERE j.id = $1
+        "#,
+        job.id
+    )
+    .fetch_one(db_pool)
+    .await?;
+
+    let ai_request = AiCritiqueRequest {
+        resume_text: keyring.open_text(
+            resume.data_key_id.as_deref(),
+            resume.data_key.as_deref(),
+            resume.original_content,
+        )?,
+        filename: resume.filename,
+        job_description: resume.job_description,
+        model: resume.model,
+        rubric: resume.rubric,
+    };
+
+    report(db_pool, job, ProgressEvent::new(job.public_id, Stage::SentToAi)).await;
+
+    let scored = scorer.score(&ai_request).await?;
+    let ai_critique = scored.critique;
+
+    let coverage = ai_request
+        .job_description
+        .as_deref()
+        .map(|jd| matching::coverage(&ai_request.resume_text, jd));
+    let fit_score = coverage.as_ref().map(|c| c.fit_score);
+    let keyword_report = coverage.as_ref().map(|c| serde_json::json!(c));
+
+    // The options only shaped the critique if the AI service wrote it
+    let (model, rubric) = if scored.generated_by == "ai" {
+        (ai_request.model.clone(), ai_request.rubric.clone())
+    } else {
+        (None, None)
+    };
+
+    report(
+        db_pool,
+        job,
+        ProgressEvent::new(job.public_id, Stage::ScoresReceived).with_data(serde_json::json!({
+            "generated_by": scored.generated_by,
+            "fallback_reason": scored.fallback_reason,
+            "overall_score": ai_critique.overall_score,
+            "fit_score": fit_score,
+            "scores": {
+                "structure": ai_critique.structure_score,
+                "keywords": ai_critique.keywords_score,
+                "action_verbs": ai_critique.action_verbs_score,
+                "quantified_impact": ai_critique.quantified_impact_score,
+                "readability": ai_critique.readability_score,
+            },
+        })),
+    )
+    .await;
+
+    // The critique and the job's completion are committed together, and only
+    // while this worker still holds the claim: if its lease lapsed and another
+    // worker took the job over, the transaction is rolled back so the job
+    // never gets a second critique.
+    let mut tx = db_pool.begin().await?;
+
+    let critique = sqlx::query_as!(
+        Critique,
+        r#"
+        INSERT INTO critiques (
+            resume_id, overall_score, structure_score, keywords_score,
+            action_verbs_score, quantified_impact_score, readability_score,
+            detailed_feedback, improvement_suggestions, generated_by, validation_issues,
+            job_description_id, fit_score, keyword_report, model, rubric, rubric_version
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
+        RETURNING id, public_id, resume_id, overall_score, structure_score, keywords_score,
+                  action_verbs_score, quantified_impact_score, readability_score,
+                  detailed_feedback, improvement_suggestions, generated_by, validation_issues,
+                  job_description_id, fit_score, keyword_report, model, rubric, created_at
+        "#,
+        job.resume_id,
+        ai_critique.overall_score,
+        ai_critique.structure_score,
+        ai_critique.keywords_score,
+        ai_critique.action_verbs_score,
+        ai_critique.quantified_impact_score,
+        ai_critique.readability_score,
+        ai_critique.detailed_feedback,
+        ai_critique.improvement_suggestions,
+        scored.generated_by,
+        serde_json::json!(scored.issues),
+        resume.job_description_id,
+        fit_score,
+        keyword_report,
+        model,
+        rubric,
+        config.rubric_version
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let claimed = sqlx::query!(
+        r#"
+        UPDATE critique_jobs
+        SET status = 'succeeded', critique_id = $2, error = NULL, error_code = NULL,
+            locked_until = NULL, finished_at = NOW()
+        WHERE id = $1 AND status = 'running' AND attempts = $3
+        "#,
+        job.id,
+        critique.id,
+        job.attempts
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    if claimed == 0 {
+        tx.rollback().await?;
+        eprintln!("Critique job {} was taken over by another worker; discarding its critique", job.id);
+        return Ok(());
+    }
+
+    sqlx::query!(
+        "UPDATE feedback_history SET critique_id = $2 WHERE resume_id = $1",
+        job.resume_id,
+        critique.id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    report(
+        db_pool,
+        job,
+        ProgressEvent::new(job.public_id, Stage::Saved).with_data(serde_json::json!({
+            "critique_id": critique.public_id,
+        })),
+    )
+    .await;
+
+    Ok(())
+}
+
+// Requeues the job with exponential backoff, or marks it failed once it has
+// used up its attempts. A job turned away by the open circuit breaker gets its
+// attempt back and waits for the breaker to close. Returns false if this
+// worker no longer holds the claim, in which case nothing is changed.
+async fn release_failed(db_pool: &PgPool, config: &Config, job: &ClaimedJob, failure: &JobFailure) -> Result<bool, sqlx::Error> {
+    let (retry_in, refund) = match failure.retry_after {
+        Some(wait) => (wait.as_secs_f64(), 1),
+        None => (RETRY_BASE_SECS * 2f64.powi(job.attempts.max(1) - 1), 0),
+    };
+
+    let result = sqlx::query!(
+        r#"
+        UPDATE critique_jobs
+        SET attempts = attempts - $6,
+            status = CASE WHEN attempts - $6 >= $4 THEN 'failed' ELSE 'queued' END,
+            finished_at = CASE WHEN attempts - $6 >= $4 THEN NOW() END,
+            available_at = NOW() + make_interval(secs => $5),
+            locked_until = NULL,
+            error = $2,
+            error_code = $3
+        WHERE id = $1 AND status = 'running' AND attempts = $7
+        "#,
+        job.id,
+        failure.message,
+        failure.code,
+        config.job_max_attempts,
+        retry_in,
+        refund,
+        job.attempts
+    )
+    .execute(db_pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{scorer, test_db};
+
+    #[sqlx::test(migrations = false)]
+    async fn mock_scorer_critiques_a_queued_job(db_pool: PgPool) {
+        test_db::schema(&db_pool).await;
+        let resume_id = test_db::resume(&db_pool, "Jane Doe\nBuilt and shipped a billing API").await;
+        let job_id = sqlx::query_scalar!("INSERT INTO critique_jobs (resume_id) VALUES ($1) RETURNING id", resume_id)
+            .fetch_one(&db_pool)
+            .await
+            .unwrap();
+
+        let config = test_db::config();
+        let keyring = Keyring::from_config(&config);
+        let job = claim_next(&db_pool).await.unwrap().expect("the queued job is claimed");
+        assert_eq!((job.id, job.attempts), (job_id, 1));
+        run_job(&db_pool, &config, &*scorer::from_config(&config), &keyring, job).await;
+
+        let result = sqlx::query!(
+            r#"
+            SELECT j.status, j.stage, c.generated_by, c.overall_score, fh.critique_id
+            FROM critique_jobs j
+            JOIN critiques c ON c.id = j.critique_id
+            JOIN feedback_history fh ON fh.resume_id = j.resume_id
+            WHERE j.id = $1
+            "#,
+            job_id
+        )
+        .fetch_one(&db_pool)
+        .await
+        .unwrap();
+        assert_eq!(result.status, "succeeded");
+        assert_eq!(result.stage, "saved");
+        assert_eq!(result.generated_by, "mock");
+        assert_eq!(result.overall_score, 3.0);
+        assert!(result.critique_id.is_some());
+        assert!(claim_next(&db_pool).await.unwrap().is_none());
+    }
+}
@@ -1,11 +1,31 @@
+mod ai_client;
 mod handlers;
 mod models;
 mod db;
 mod auth;
 mod config;
+mod content;
+mod crypto;
+mod diff;
+mod docx;
+mod jobs;
+mod matching;
+mod pdf;
+mod progress;
+mod redact;
+mod reencrypt;
+mod retention;
+mod scorer;
+mod scoring;
+mod storage;
+#[cfg(test)]
+mod test_db;
+mod upload;
+mod validation;
 
 use warp::Filter;
 use std::sync::Arc;
+use uuid::Uuid;
 
 #[tokio::main]
 async fn main() {
@@ -14,6 +34,41 @@ async fn main() {
 
     let config = Arc::new(config::Config::from_env());
     let db_pool = Arc::new(db::create_pool(&config.database_url).await.expect("Failed to create database pool"));
+    let storage = storage::from_config(&config);
+    let keyring = Arc::new(crypto::Keyring::from_config(&config));
+
+    // `reencrypt` moves stored resumes onto the active encryption key and exits
+    if std::env::args().nth(1).as_deref() == Some("reencrypt") {
+        if let Err(e) = reencrypt::run(&db_pool, &config, storage, &keyring).await {
+            eprintln!("{:#}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    // `purge [--dry-run]` applies the retention windows once and exits
+    if std::env::args().nth(1).as_deref() == Some("purge") {
+        if !retention::enabled(&config) {
+            eprintln!("Set RETENTION_DAYS or RETENTION_DAYS_BY_ROLE to purge old resumes");
+            std::process::exit(1);
+        }
+        let dry_run = std::env::args().nth(2).as_deref() == Some("--dry-run");
+        if let Err(e) = retention::purge(&db_pool, &config, &*storage, dry_run).await {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if keyring.active_key_id().is_none() {
+        eprintln!("ENCRYPTION_KEYS is not set; uploads are stored unencrypted");
+    }
+
+    let job_queue = Arc::new(jobs::JobQueue::new());
+    let scorer = scorer::from_config(&config);
+    jobs::spawn_workers(db_pool.clone(), config.clone(), job_queue.clone(), scorer, keyring.clone());
+    let progress_hub = Arc::new(progress::ProgressHub::new());
+    progress::spawn_listener(db_pool.clone(), progress_hub.clone());
+    retention::spawn_purger(db_pool.clone(), config.clone(), storage.clone());
+    let extractor = Arc::new(content::Extractor::new(config.max_concurrent_extractions));
 
     // CORS configuration
     let cors = warp::cors()
@@ -22,19 +77,119 @@ async fn main() {
         .allow_methods(vec!["GET", "POST", "PUT", "DELETE", "OPTIONS"]);
 
     // Routes
+    // Uploads require a signed-in user; anonymous requests are rejected with
+    // 401 before the body is read. The file itself is held to
+    // MAX_FILE_SIZE as it streams; the form limit leaves room for the
+    // other fields.
     let upload_route = warp::path("upload-resume")
         .and(warp::post())
-        .and(warp::multipart::form().max_length(config.max_file_size))
+        .and(auth::with_auth(config.clone()))
+        .and(warp::multipart::form().max_length(config.max_file_size + 64 * 1024))
         .and(with_db(db_pool.clone()))
         .and(with_config(config.clone()))
+        .and(with_job_queue(job_queue.clone()))
+        .and(with_storage(storage.clone()))
+        .and(with_keyring(keyring.clone()))
+        .and(with_extractor(extractor))
         .and_then(handlers::upload_resume);
 
-    let critique_route = warp::path!("get-critique" / i32)
+    let job_route = warp::path!("jobs" / Uuid)
         .and(warp::get())
+        .and(auth::with_auth(config.clone()))
+        .and(with_db(db_pool.clone()))
+        .and_then(handlers::get_job);
+
+    let job_events_route = warp::path!("jobs" / Uuid / "events")
+        .and(warp::get())
+        .and(auth::with_stream_auth(config.clone()))
+        .and(with_db(db_pool.clone()))
+        .and(with_progress_hub(progress_hub.clone()))
+        .and_then(handlers::get_job_events);
+
+    let critique_route = warp::path!("get-critique" / Uuid)
+        .and(warp::get())
+        .and(auth::with_auth(config.clone()))
         .and(with_db(db_pool.clone()))
         .and(with_config(config.clone()))
         .and_then(handlers::get_critique);
 
+    let critique_diff_route = warp::path!("critiques" / Uuid / "diff" / Uuid)
+        .and(warp::get())
+        .and(auth::with_auth(config.clone()))
+        .and(with_db(db_pool.clone()))
+        .and(with_keyring(keyring.clone()))
+        .and_then(handlers::diff_critiques);
+
+    let job_descriptions_route = warp::path!("job-descriptions")
+        .and(warp::post())
+        .and(auth::with_auth(config.clone()))
+        .and(warp::body::content_length_limit(64 * 1024))
+        .and(warp::body::json())
+        .and(with_db(db_pool.clone()))
+        .and_then(handlers::create_job_description)
+        .or(warp::path!("job-descriptions")
+            .and(warp::get())
+            .and(auth::with_auth(config.clone()))
+            .and(with_db(db_pool.clone()))
+            .and_then(handlers::list_job_descriptions))
+        .or(warp::path!("job-descriptions" / Uuid)
+            .and(warp::get())
+            .and(auth::with_auth(config.clone()))
+            .and(with_db(db_pool.clone()))
+            .and_then(handlers::get_job_description));
+
+    let resume_routes = warp::path!("resumes")
+        .and(warp::get())
+        .and(auth::with_auth(config.clone()))
+        .and(with_db(db_pool.clone()))
+        .and_then(handlers::list_resumes)
+        .or(warp::path!("resumes" / Uuid)
+            .and(warp::get())
+            .and(auth::with_auth(config.clone()))
+            .and(with_db(db_pool.clone()))
+            .and(with_keyring(keyring.clone()))
+            .and_then(handlers::get_resume))
+        .or(warp::path!("resumes" / Uuid / "file")
+            .and(warp::get())
+            .and(auth::with_auth(config.clone()))
+            .and(with_db(db_pool.clone()))
+            .and(with_storage(storage.clone()))
+            .and(with_keyring(keyring.clone()))
+            .and_then(handlers::download_resume))
+        .or(warp::path!("resumes" / Uuid)
+            .and(warp::delete())
+            .and(auth::with_auth(config.clone()))
+            .and(with_db(db_pool.clone()))
+            .and(with_storage(storage.clone()))
+            .and_then(handlers::delete_resume))
+        .or(warp::path!("resumes" / Uuid / "critiques")
+            .and(warp::post())
+            .and(auth::with_auth(config.clone()))
+            .and(warp::body::content_length_limit(16 * 1024))
+            .and(warp::body::json())
+            .and(with_db(db_pool.clone()))
+            .and(with_config(config.clone()))
+            .and(with_job_queue(job_queue.clone()))
+            .and_then(handlers::create_resume_critique));
+
+    let version_routes = warp::path!("resumes" / Uuid / "versions")
+        .and(warp::get())
+        .and(auth::with_auth(config.clone()))
+        .and(with_db(db_pool.clone()))
+        .and_then(handlers::list_versions)
+        .or(warp::path!("resumes" / Uuid / "versions" / i32 / "notes")
+            .and(warp::put())
+            .and(auth::with_auth(config.clone()))
+            .and(warp::body::content_length_limit(16 * 1024))
+            .and(warp::body::json())
+            .and(with_db(db_pool.clone()))
+            .and_then(handlers::update_version_notes))
+        .or(warp::path!("resumes" / Uuid / "versions" / i32 / "critique")
+            .and(warp::get())
+            .and(auth::with_auth(config.clone()))
+            .and(with_db(db_pool.clone()))
+            .and_then(handlers::get_version_critique));
+
     let auth_routes = warp::path("auth")
         .and(
             warp::path("login")
@@ -59,16 +214,31 @@ async fn main() {
                 )
         );
 
-    let history_route = warp::path("history")
+    let history_route = warp::path!("history")
         .and(warp::get())
         .and(auth::with_auth(config.clone()))
+        .and(warp::query::<models::HistoryQuery>())
         .and(with_db(db_pool.clone()))
         .and_then(handlers::get_history);
 
+    let analytics_route = warp::path!("analytics")
+        .and(warp::get())
+        .and(auth::with_auth(config.clone()))
+        .and(warp::query::<models::AnalyticsQuery>())
+        .and(with_db(db_pool.clone()))
+        .and_then(handlers::get_analytics);
+
     let routes = upload_route
         .or(critique_route)
+        .or(critique_diff_route)
+        .or(job_route)
+        .or(job_events_route)
+        .or(job_descriptions_route)
+        .or(resume_routes)
+        .or(version_routes)
         .or(auth_routes)
         .or(history_route)
+        .or(analytics_route)
         .with(cors)
         .recover(handlers::handle_rejection)
         .with(warp::log("resume-critique-backend"));
@@ -84,3 +254,23 @@ fn with_db(db_pool: Arc<sqlx::PgPool>) -> impl Filter<Extract = (Arc<sqlx::PgPoo
 fn with_config(config: Arc<config::Config>) -> impl Filter<Extract = (Arc<config::Config>,), Error = std::convert::Infallible> + Clone {
     warp::any().map(move || config.clone())
 }
+
+fn with_job_queue(job_queue: Arc<jobs::JobQueue>) -> impl Filter<Extract = (Arc<jobs::JobQueue>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || job_queue.clone())
+}
+
+fn with_storage(storage: Arc<dyn storage::Storage>) -> impl Filter<Extract = (Arc<dyn storage::Storage>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || storage.clone())
+}
+
+fn with_keyring(keyring: Arc<crypto::Keyring>) -> impl Filter<Extract = (Arc<crypto::Keyring>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || keyring.clone())
+}
+
+fn with_extractor(extractor: Arc<content::Extractor>) -> impl Filter<Extract = (Arc<content::Extractor>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || extractor.clone())
+}
+
+fn with_progress_hub(progress_hub: Arc<progress::ProgressHub>) -> impl Filter<Extract = (Arc<progress::ProgressHub>,), Error = std::convert::Infallible> + Clone {
+    warp::any().map(move || progress_hub.clone())
+}
@@ -0,0 +1,314 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+use crate::scoring::KEYWORDS;
+
+// Compares a resume against a job description: which of the posting's key
+// terms the resume uses verbatim, which only in another form, and which not
+// at all. Purely lexical, so it works offline and is the same whichever
+// scorer produced the critique.
+
+const MAX_TERMS: usize = 30;
+const MAX_LOCATIONS: usize = 5;
+
+// Words that carry no signal about the role, on top of ordinary stopwords.
+const STOPWORDS: &[&str] = &[
+    "a", "about", "across", "all", "also", "an", "and", "any", "are", "as", "at", "be", "been", "both", "but",
+    "by", "can", "do", "each", "etc", "for", "from", "has", "have", "how", "if", "in", "including", "into",
+    "is", "it", "its", "just", "may", "more", "most", "must", "new", "not", "of", "on", "one", "or", "other",
+    "our", "out", "over", "own", "per", "plus", "should", "so", "such", "than", "that", "the", "their", "them",
+    "then", "there", "these", "they", "this", "those", "through", "to", "up", "us", "using", "was", "we",
+    "well", "were", "what", "when", "where", "which", "while", "who", "will", "with", "within", "would",
+    "you", "your", "ability", "able", "applicant", "apply", "candidate", "candidates", "company", "day",
+    "environment", "equal", "excellent", "experience", "good", "great", "ideal", "job", "join", "looking",
+    "opportunity", "position", "preferred", "qualifications", "required", "requirements", "responsibilities",
+    "role", "skills", "strong", "team", "work", "working", "year", "years",
+];
+
+#[derive(Debug, Serialize)]
+pub struct CoverageReport {
+    pub matched: Vec<TermMatch>,
+    pub partial: Vec<TermMatch>,
+    pub missing: Vec<String>,
+    // Share of the posting's weighted terms found in the resume, 0-5
+    pub fit_score: f32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TermMatch {
+    pub term: String,
+    // The form found in the resume, when it differs from the term
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub found_as: Option<String>,
+    pub locations: Vec<Location>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Location {
+    // 1-based line number in the extracted resume text
+    pub line: usize,
+    pub section: Option<String>,
+}
+
+struct Term {
+    text: String,
+    words: Vec<String>,
+    weight: f32,
+}
+
+pub fn coverage(resume_text: &str, job_description: &str) -> CoverageReport {
+    let terms = key_terms(job_description);
+    let lines = index_resume(resume_text);
+
+    let mut matched = Vec::new();
+    let mut partial = Vec::new();
+    let mut missing = Vec::new();
+    let mut total = 0.0;
+    let mut covered = 0.0;
+
+    for term in terms {
+        total += term.weight;
+
+        let exact = find(&lines, |words| contains_phrase(words, &term.words));
+        if !exact.is_empty() {
+            covered += term.weight;
+            matched.push(TermMatch {
+                term: term.text,
+                found_as: None,
+                locations: exact,
+            });
+            continue;
+        }
+
+        // Another inflection ("managed" for "management"), or for a phrase,
+        // some of its words.
+        let stems: Vec<String> = term.words.iter().map(|w| stem(w)).collect();
+        let mut found_as = None;
+        let loose = find(&lines, |words| {
+            let hit = if stems.len() == 1 {
+                words.iter().find(|w| stem(w) == stems[0]).cloned()
+            } else {
+                words.iter().find(|w| stems.contains(&stem(w)) && !STOPWORDS.contains(&w.as_str())).cloned()
+            };
+            if let Some(word) = &hit {
+                found_as.get_or_insert_with(|| word.clone());
+            }
+            hit.is_some()
+        });
+
+        if loose.is_empty() {
+            missing.push(term.text);
+        } else {
+            covered += term.weight * 0.5;
+            partial.push(TermMatch {
+                term: term.text,
+                found_as,
+                locations: loose,
+            });
+        }
+    }
+
+    let fit_score = if total > 0.0 { (covered / total * 5.0 * 10.0).round() / 10.0 } else { 0.0 };
+
+    CoverageReport {
+        matched,
+        partial,
+        missing,
+        fit_score,
+    }
+}
+
+// Picks the posting's most important terms: known skills and tools first,
+// then words and two-word phrases by how often the posting repeats them.
+fn key_terms(job_description: &str) -> Vec<Term> {
+    let words = tokenize(&job_description.to_lowercase());
+    let known: HashSet<&str> = KEYWORDS.iter().copied().collect();
+
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for word in &words {
+        if is_candidate(word) {
+            *counts.entry(word.clone()).or_default() += 1;
+        }
+    }
+    for pair in words.windows(2) {
+        if is_candidate(&pair[0]) && is_candidate(&pair[1]) {
+            *counts.entry(format!("{} {}", pair[0], pair[1])).or_default() += 1;
+        }
+    }
+
+    let mut terms: Vec<Term> = counts
+        .into_iter()
+        .filter(|(text, count)| {
+            // A phrase only counts if it is a known skill or is repeated.
+            known.contains(text.as_str()) || !text.contains(' ') || *count >= 2
+        })
+        .map(|(text, count)| {
+            let weight = count as f32 + if known.contains(text.as_str()) { 3.0 } else { 0.0 };
+            Term {
+                words: text.split(' ').map(str::to_string).collect(),
+                text,
+                weight,
+            }
+        })
+        .collect();
+
+    // Drop single words already covered by a chosen phrase.
+    let phrases: HashSet<String> = terms
+        .iter()
+        .filter(|t| t.words.len() > 1)
+        .flat_map(|t| t.words.iter().cloned())
+        .collect();
+    terms.retain(|t| t.words.len() > 1 || known.contains(t.text.as_str()) || !phrases.contains(&t.text));
+
+    // Heaviest first, alphabetical on ties so the report is stable.
+    terms.sort_by(|a, b| b.weight.total_cmp(&a.weight).then_with(|| a.text.cmp(&b.text)));
+    terms.truncate(MAX_TERMS);
+    terms
+}
+
+fn is_candidate(word: &str) -> bool {
+    word.len() >= 2 && !STOPWORDS.contains(&word) && word.chars().any(char::is_alphabetic)
+}
+
+// Lowercased words with the characters skill names use (c++, c#, node.js,
+// ci/cd) kept intact.
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !(c.is_alphanumeric() || "+#./&".contains(c)))
+        .map(|w| w.trim_matches(|c: char| c == '.' || c == '/'))
+        .filter(|w| !w.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+struct ResumeLine {
+    number: usize,
+    section: Option<String>,
+    words: Vec<String>,
+}
+
+fn index_resume(text: &str) -> Vec<ResumeLine> {
+    let mut section = None;
+    let mut lines = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if is_heading(trimmed) {
+            section = Some(trimmed.trim_end_matches(':').to_lowercase());
+        }
+        lines.push(ResumeLine {
+            number: i + 1,
+            section: section.clone(),
+            words: tokenize(&trimmed.to_lowercase()),
+        });
+    }
+    lines
+}
+
+// Short lines in capitals or ending with a colon are treated as headings.
+fn is_heading(line: &str) -> bool {
+    let letters: Vec<char> = line.chars().filter(|c| c.is_alphabetic()).collect();
+    line.split_whitespace().count() <= 4
+        && !letters.is_empty()
+        && (line.ends_with(':') || letters.iter().all(|c| c.is_uppercase()))
+}
+
+fn find(lines: &[ResumeLine], mut matches: impl FnMut(&[String]) -> bool) -> Vec<Location> {
+    lines
+        .iter()
+        .filter(|line| matches(&line.words))
+        .take(MAX_LOCATIONS)
+        .map(|line| Location {
+            line: line.number,
+            section: line.section.clone(),
+        })
+        .collect()
+}
+
+fn contains_phrase(words: &[String], phrase: &[String]) -> bool {
+    words.windows(phrase.len()).any(|window| window == phrase)
+}
+
+// A light suffix stripper; enough to equate "managed", "manager" and
+// "management" without a stemming library.
+fn stem(word: &str) -> String {
+    const SUFFIXES: &[&str] = &["ments", "ment", "ings", "ing", "ions", "ion", "ers", "er", "ed", "es", "s"];
+    for suffix in SUFFIXES {
+        if let Some(root) = word.strip_suffix(suffix) {
+            if root.len() >= 3 {
+                return root.trim_end_matches('e').to_string();
+            }
+        }
+    }
+    word.trim_end_matches('e').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESUME: &str = "\
+Jane Doe
+
+SKILLS
+Rust, Docker
+
+Experience:
+Managed stakeholders across three teams
+Rust backend services";
+
+    const POSTING: &str = "Rust developer. Kubernetes required. Stakeholder management.";
+
+    fn lines(locations: &[Location]) -> Vec<(usize, Option<&str>)> {
+        locations.iter().map(|l| (l.line, l.section.as_deref())).collect()
+    }
+
+    #[test]
+    fn sorts_terms_into_matched_partial_and_missing() {
+        let report = coverage(RESUME, POSTING);
+
+        let matched: Vec<&str> = report.matched.iter().map(|m| m.term.as_str()).collect();
+        assert_eq!(matched, ["rust"]);
+        let partial: Vec<(&str, Option<&str>)> =
+            report.partial.iter().map(|m| (m.term.as_str(), m.found_as.as_deref())).collect();
+        assert_eq!(partial, [("stakeholder", Some("stakeholders")), ("management", Some("managed"))]);
+        assert_eq!(report.missing, ["kubernetes", "developer"]);
+
+        // Known skills weigh 4, other words 1; a partial match counts half:
+        // (4 + 4 * 0.5 + 1 * 0.5) / 14 of 5
+        assert_eq!(report.fit_score, 2.3);
+    }
+
+    #[test]
+    fn reports_line_and_section() {
+        let report = coverage(RESUME, POSTING);
+        assert_eq!(lines(&report.matched[0].locations), [(4, Some("skills")), (8, Some("experience"))]);
+        assert_eq!(lines(&report.partial[0].locations), [(7, Some("experience"))]);
+    }
+
+    #[test]
+    fn locations_are_capped() {
+        let resume = "Rust\n".repeat(MAX_LOCATIONS + 3);
+        let report = coverage(&resume, "Rust");
+        assert_eq!(report.matched[0].locations.len(), MAX_LOCATIONS);
+        assert_eq!(report.matched[0].locations[0].section, None);
+        assert_eq!(report.fit_score, 5.0);
+    }
+
+    #[test]
+    fn empty_postings_fit_nothing() {
+        for posting in ["", "   \n", "We are looking for a great team player who will join us."] {
+            let report = coverage(RESUME, posting);
+            assert!(report.matched.is_empty() && report.partial.is_empty(), "{:?}", report);
+            assert_eq!(report.fit_score, 0.0);
+        }
+    }
+
+    #[test]
+    fn keeps_skill_punctuation() {
+        assert_eq!(tokenize("c++, c#, node.js and ci/cd."), ["c++", "c#", "node.js", "and", "ci/cd"]);
+        assert_eq!(stem("management"), stem("managed"));
+        assert_eq!(coverage("Node.js and C++", "node.js c++").missing, Vec::<String>::new());
+    }
+}
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use sqlx::FromRow;
+use uuid::Uuid;
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct User {
@@ -8,6 +9,7 @@ pub struct User {
     pub email: String,
     pub password_hash: String,
     pub name: String,
+    pub role: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -15,10 +17,11 @@ pub struct User {
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct Resume {
     pub id: i32,
+    pub public_id: Uuid,
     pub user_id: i32,
     pub filename: String,
     pub original_content: String,
-    pub file_path: Option<String>,
+    pub storage_key: Option<String>,
     pub file_size: Option<i32>,
     pub mime_type: Option<String>,
     pub uploaded_at: DateTime<Utc>,
@@ -27,6 +30,7 @@ pub struct Resume {
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct Critique {
     pub id: i32,
+    pub public_id: Uuid,
     pub resume_id: i32,
     pub overall_score: f32,
     pub structure_score: f32,
@@ -36,6 +40,24 @@ pub struct Critique {
     pub readability_score: f32,
     pub detailed_feedback: serde_json::Value,
     pub improvement_suggestions: serde_json::Value,
+    pub generated_by: String,
+    pub validation_issues: serde_json::Value,
+    pub job_description_id: Option<i32>,
+    pub fit_score: Option<f32>,
+    pub keyword_report: Option<serde_json::Value>,
+    pub model: Option<String>,
+    pub rubric: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct JobDescription {
+    pub id: i32,
+    pub public_id: Uuid,
+    pub user_id: i32,
+    pub title: String,
+    pub company: Option<String>,
+    pub description: String,
     pub created_at: DateTime<Utc>,
 }
 
@@ -44,7 +66,9 @@ pub struct FeedbackHistory {
     pub id: i32,
     pub user_id: i32,
     pub resume_id: i32,
-    pub critique_id: i32,
+    pub critique_id: Option<i32>,
+    // Groups the successive versions of one resume
+    pub lineage_id: Uuid,
     pub version_number: i32,
     pub notes: Option<String>,
     pub created_at: DateTime<Utc>,
@@ -79,12 +103,23 @@ pub struct UserResponse {
 
 #[derive(Debug, Serialize)]
 pub struct CritiqueResponse {
-    pub id: i32,
+    pub id: Uuid,
     pub resume_filename: String,
     pub overall_score: f32,
     pub scores: CritiqueScores,
     pub detailed_feedback: serde_json::Value,
     pub improvement_suggestions: serde_json::Value,
+    // "ai", or "heuristic" when the AI service was unavailable
+    pub generated_by: String,
+    // Corrections applied to the AI output before it was stored
+    pub validation_issues: serde_json::Value,
+    // Set when the critique was requested against a job description
+    pub job_description_id: Option<Uuid>,
+    pub fit_score: Option<f32>,
+    pub keyword_report: Option<serde_json::Value>,
+    // AI options the critique was requested with; None for the defaults
+    pub model: Option<String>,
+    pub rubric: Option<String>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -100,19 +135,316 @@ pub struct CritiqueScores {
 #[derive(Debug, Serialize)]
 pub struct UploadResponse {
     pub message: String,
-    pub critique_id: i32,
+    pub resume_id: Uuid,
+    pub version_number: i32,
+    // Set when a critique was queued
+    pub job_id: Option<Uuid>,
+    // Set instead when an earlier critique of the same content was reused
+    pub critique_id: Option<Uuid>,
+    // The file was identical to an earlier upload, whose resume is returned
+    pub duplicate: bool,
+    pub cached: bool,
+}
+
+// Options for critiquing an uploaded resume again; all may be omitted
+#[derive(Debug, Deserialize)]
+pub struct CreateCritiqueRequest {
+    pub job_description_id: Option<Uuid>,
+    // One of AI_MODELS
+    pub model: Option<String>,
+    // Extra reviewing instructions for the AI service
+    pub rubric: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CritiqueQueuedResponse {
+    pub message: String,
+    pub resume_id: Uuid,
+    pub job_id: Uuid,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResumeResponse {
+    pub id: Uuid,
+    pub filename: String,
+    pub file_size: Option<i32>,
+    pub mime_type: Option<String>,
+    pub lineage_id: Uuid,
+    pub version_number: i32,
+    pub critique_count: i64,
+    pub latest_critique_id: Option<Uuid>,
+    pub latest_overall_score: Option<f32>,
+    pub uploaded_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResumeListResponse {
+    pub resumes: Vec<ResumeResponse>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResumeCritiqueSummary {
+    pub id: Uuid,
+    pub overall_score: f32,
+    pub generated_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResumeDetailResponse {
+    #[serde(flatten)]
+    pub resume: ResumeResponse,
+    // Text extracted from the file at upload
+    pub content: String,
+    // Newest first
+    pub critiques: Vec<ResumeCritiqueSummary>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JobResponse {
+    pub id: Uuid,
+    pub status: String,
+    pub attempts: i32,
+    pub critique_id: Option<Uuid>,
+    pub error: Option<String>,
+    pub error_code: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateJobDescriptionRequest {
+    pub title: String,
+    pub company: Option<String>,
+    pub description: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JobDescriptionResponse {
+    pub id: Uuid,
+    pub title: String,
+    pub company: Option<String>,
+    pub description: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<JobDescription> for JobDescriptionResponse {
+    fn from(jd: JobDescription) -> Self {
+        Self {
+            id: jd.public_id,
+            title: jd.title,
+            company: jd.company,
+            description: jd.description,
+            created_at: jd.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct JobDescriptionListResponse {
+    pub job_descriptions: Vec<JobDescriptionResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VersionNotesRequest {
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResumeVersionResponse {
+    pub version_number: i32,
+    pub resume_id: Uuid,
+    pub filename: String,
+    pub notes: Option<String>,
+    // Latest critique of this version, once one has completed
+    pub critique_id: Option<Uuid>,
+    pub overall_score: Option<f32>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VersionListResponse {
+    pub lineage_id: Uuid,
+    pub versions: Vec<ResumeVersionResponse>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScoreDelta {
+    pub before: f32,
+    pub after: f32,
+    pub delta: f32,
+}
+
+impl ScoreDelta {
+    pub fn new(before: f32, after: f32) -> Self {
+        Self {
+            before,
+            after,
+            delta: ((after - before) * 10.0).round() / 10.0,
+        }
+    }
+}
+
+// Per-dimension changes, mirroring CritiqueScores
+#[derive(Debug, Serialize)]
+pub struct ScoreDeltas {
+    pub overall: ScoreDelta,
+    pub structure: ScoreDelta,
+    pub keywords: ScoreDelta,
+    pub action_verbs: ScoreDelta,
+    pub quantified_impact: ScoreDelta,
+    pub readability: ScoreDelta,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CritiqueDiffResponse {
+    // The earlier of the two critiques, whichever order they were given in
+    pub from_critique_id: Uuid,
+    pub to_critique_id: Uuid,
+    pub from_filename: String,
+    pub to_filename: String,
+    pub scores: ScoreDeltas,
+    pub suggestions: crate::diff::SuggestionChanges,
+    // None when a resume is too long to diff
+    pub content_diff: Option<Vec<crate::diff::DiffChunk>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AnalyticsQuery {
+    // Number of critiques averaged for each point of the moving average
+    pub window: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnalyticsPoint {
+    pub critique_id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub overall_score: f32,
+    pub scores: CritiqueScores,
+    pub moving_average: MovingAverage,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MovingAverage {
+    pub overall_score: f32,
+    pub scores: CritiqueScores,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DimensionTrend {
+    pub dimension: String,
+    pub average: f32,
+    pub min: f32,
+    pub max: f32,
+    pub first: f32,
+    pub latest: f32,
+    // Least-squares slope of the score, per critique and per 30 days. The
+    // per-critique figure needs two critiques; the per-30-days one a week of
+    // history, since uploads minutes apart would otherwise dominate it.
+    pub velocity_per_critique: Option<f32>,
+    pub velocity_per_30_days: Option<f32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnalyticsResponse {
+    pub critique_count: usize,
+    pub window: i64,
+    // Highest and lowest average among the five scoring categories
+    pub best_dimension: Option<String>,
+    pub worst_dimension: Option<String>,
+    pub dimensions: Vec<DimensionTrend>,
+    pub series: Vec<AnalyticsPoint>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HistorySort {
+    #[default]
+    CreatedAt,
+    OverallScore,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HistoryQuery {
+    pub limit: Option<i64>,
+    // `next_cursor` from the previous page
+    pub cursor: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub min_score: Option<f32>,
+    pub max_score: Option<f32>,
+    // Case-insensitive substring of the resume filename
+    pub filename: Option<String>,
+    pub resume_id: Option<Uuid>,
+    #[serde(default)]
+    pub sort: HistorySort,
+    #[serde(default)]
+    pub order: SortOrder,
+    // Adds the feedback blobs to each entry
+    #[serde(default)]
+    pub include_feedback: bool,
+}
+
+// Position after the last entry of a page. Sent to clients base64-encoded
+// and opaque; it carries the sort it was made for so it can't be replayed
+// against a different ordering.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct HistoryCursor {
+    pub sort: HistorySort,
+    pub order: SortOrder,
+    pub key: f64,
+    pub id: Uuid,
+}
+
+// A critique as listed in the history. The feedback fields are only present
+// when requested with `include_feedback=true`.
+#[derive(Debug, Serialize)]
+pub struct HistoryEntry {
+    pub id: Uuid,
+    pub resume_id: Uuid,
+    pub resume_filename: String,
+    pub overall_score: f32,
+    pub scores: CritiqueScores,
+    pub generated_by: String,
+    pub job_description_id: Option<Uuid>,
+    pub fit_score: Option<f32>,
+    pub created_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detailed_feedback: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub improvement_suggestions: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validation_issues: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keyword_report: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct HistoryResponse {
-    pub critiques: Vec<CritiqueResponse>,
+    pub critiques: Vec<HistoryEntry>,
+    pub next_cursor: Option<String>,
 }
 
 // AI Service Request/Response
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct AiCritiqueRequest {
     pub resume_text: String,
     pub filename: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rubric: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
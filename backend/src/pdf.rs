@@ -0,0 +1,458 @@
+use pdf_extract::{Document, MediaBox, OutputDev, OutputError, Transform};
+use std::fmt;
+
+// Below this many alphanumeric characters we assume the PDF is a scan or an
+// image export rather than a text document.
+const MIN_TEXT_CHARS: usize = 20;
+
+#[derive(Debug)]
+pub enum PdfError {
+    Encrypted,
+    ImageOnly,
+    Malformed(String),
+}
+
+impl fmt::Display for PdfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PdfError::Encrypted => write!(f, "PDF is password protected; please upload an unencrypted copy"),
+            PdfError::ImageOnly => write!(f, "PDF contains no extractable text; it appears to be a scanned or image-only document"),
+            PdfError::Malformed(e) => write!(f, "PDF could not be parsed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for PdfError {}
+
+// Extracts plain text from a PDF held in memory. Pages are laid out
+// independently: glyphs are grouped into lines by baseline, and two-column
+// layouts (a common resume sidebar design) are read column by column rather
+// than across the gutter. Font decoding, including embedded fonts and
+// ToUnicode maps, is handled by pdf-extract.
+pub fn extract_text(data: &[u8]) -> Result<String, PdfError> {
+    let mut doc = Document::load_mem(data).map_err(|e| PdfError::Malformed(e.to_string()))?;
+
+    // Many "protected" PDFs only carry an owner password restricting editing;
+    // those open with an empty user password.
+    if doc.is_encrypted() && doc.decrypt("").is_err() {
+        return Err(PdfError::Encrypted);
+    }
+
+    let mut collector = PageCollector::default();
+    pdf_extract::output_doc(&doc, &mut collector).map_err(|e| PdfError::Malformed(e.to_string()))?;
+
+    let text = collector
+        .pages
+        .iter()
+        .map(|page| page.trim())
+        .filter(|page| !page.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    if text.chars().filter(|c| c.is_alphanumeric()).count() < MIN_TEXT_CHARS {
+        return Err(PdfError::ImageOnly);
+    }
+
+    Ok(text)
+}
+
+struct Glyph {
+    x: f64,
+    y: f64,
+    width: f64,
+    size: f64,
+    text: String,
+}
+
+#[derive(Default)]
+struct PageCollector {
+    page_height: f64,
+    glyphs: Vec<Glyph>,
+    pages: Vec<String>,
+}
+
+impl OutputDev for PageCollector {
+    fn begin_page(&mut self, _page_num: u32, media_box: &MediaBox, _art_box: Option<(f64, f64, f64, f64)>) -> Result<(), OutputError> {
+        self.page_height = media_box.ury - media_box.lly;
+        self.glyphs.clear();
+        Ok(())
+    }
+
+    fn end_page(&mut self) -> Result<(), OutputError> {
+        let glyphs = std::mem::take(&mut self.glyphs);
+        self.pages.push(layout_page(glyphs));
+        Ok(())
+    }
+
+    fn output_character(&mut self, trm: &Transform, width: f64, _spacing: f64, font_size: f64, char: &str) -> Result<(), OutputError> {
+        // Glyph widths are in text space units; the text rendering matrix
+        // scales them (and the font size) into page space.
+        let scale_x = trm.m11.hypot(trm.m12) * font_size;
+        let size = (trm.m11 * trm.m22 - trm.m12 * trm.m21).abs().sqrt() * font_size;
+        if size <= 0.0 {
+            return Ok(());
+        }
+
+        self.glyphs.push(Glyph {
+            x: trm.m31,
+            y: self.page_height - trm.m32,
+            width: width * scale_x,
+            size,
+            text: normalize_ligatures(char),
+        });
+        Ok(())
+    }
+
+    fn begin_word(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn end_word(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn end_line(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+}
+
+// A run of glyphs on one baseline with no large horizontal gap.
+struct Segment {
+    x_min: f64,
+    x_max: f64,
+    size: f64,
+    text: String,
+}
+
+struct Line {
+    segments: Vec<Segment>,
+}
+
+fn layout_page(mut glyphs: Vec<Glyph>) -> String {
+    glyphs.retain(|g| !g.text.trim().is_empty());
+    if glyphs.is_empty() {
+        return String::new();
+    }
+
+    // Top to bottom, then left to right.
+    glyphs.sort_by(|a, b| a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x)));
+
+    let mut rows: Vec<Vec<Glyph>> = Vec::new();
+    for glyph in glyphs {
+        match rows.last_mut() {
+            Some(row) if (glyph.y - row[0].y).abs() <= row[0].size.min(glyph.size) * 0.5 => row.push(glyph),
+            _ => rows.push(vec![glyph]),
+        }
+    }
+
+    let lines: Vec<Line> = rows.into_iter().map(build_line).collect();
+
+    match find_gutter(&lines) {
+        Some(gutter) => read_columns(&lines, gutter),
+        None => lines.iter().map(line_text).collect::<Vec<_>>().join("\n"),
+    }
+}
+
+fn build_line(mut row: Vec<Glyph>) -> Line {
+    row.sort_by(|a, b| a.x.total_cmp(&b.x));
+
+    let mut segments: Vec<Segment> = Vec::new();
+    let mut last_end = f64::NEG_INFINITY;
+    for glyph in row {
+        let gap = glyph.x - last_end;
+        match segments.last_mut() {
+            // A gap wider than a few ems is a column boundary or a tab stop.
+            Some(segment) if gap <= glyph.size * 2.5 => {
+                if gap > glyph.size * 0.15 {
+                    segment.text.push(' ');
+                }
+                segment.text.push_str(&glyph.text);
+                segment.x_max = segment.x_max.max(glyph.x + glyph.width);
+            }
+            _ => segments.push(Segment {
+                x_min: glyph.x,
+                x_max: glyph.x + glyph.width,
+                size: glyph.size,
+                text: glyph.text.clone(),
+            }),
+        }
+        last_end = glyph.x + glyph.width;
+    }
+
+    Line { segments }
+}
+
+fn line_text(line: &Line) -> String {
+    line.segments.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join("  ")
+}
+
+// Looks for a vertical strip that most lines leave empty and that has text on
+// both sides of it. Returns its x coordinate.
+fn find_gutter(lines: &[Line]) -> Option<f64> {
+    let left = lines.iter().flat_map(|l| &l.segments).map(|s| s.x_min).fold(f64::INFINITY, f64::min);
+    let right = lines.iter().flat_map(|l| &l.segments).map(|s| s.x_max).fold(f64::NEG_INFINITY, f64::max);
+    let width = right - left;
+    if lines.len() < 4 || width <= 0.0 {
+        return None;
+    }
+
+    const BINS: usize = 200;
+    let mut coverage = [0usize; BINS];
+    for segment in lines.iter().flat_map(|l| &l.segments) {
+        let start = (((segment.x_min - left) / width) * BINS as f64) as usize;
+        let end = ((((segment.x_max - left) / width) * BINS as f64).ceil() as usize).min(BINS);
+        for bin in &mut coverage[start.min(BINS)..end] {
+            *bin += 1;
+        }
+    }
+
+    // Full-width headings and footers may cross the gutter, so allow a few.
+    let allowed = (lines.len() / 10).max(1);
+    let lo = BINS / 5;
+    let hi = BINS * 4 / 5;
+
+    let mut best: Option<(usize, usize)> = None;
+    let mut run_start = None;
+    for (bin, &count) in coverage.iter().enumerate().take(hi).skip(lo) {
+        if count <= allowed {
+            let start = *run_start.get_or_insert(bin);
+            let len = bin - start + 1;
+            if best.is_none_or(|(_, best_len)| len > best_len) {
+                best = Some((start, len));
+            }
+        } else {
+            run_start = None;
+        }
+    }
+
+    // Require a gutter of at least 2% of the text width.
+    let (start, len) = best.filter(|&(_, len)| len >= BINS / 50)?;
+    let gutter = left + (start as f64 + len as f64 / 2.0) / BINS as f64 * width;
+
+    // A real column is left-aligned along its edge. Right-aligned dates next
+    // to job titles also leave a gap, but their left edges are ragged.
+    let column = |side: fn(&Segment, f64) -> bool| {
+        let segments: Vec<&Segment> = lines.iter().flat_map(|l| &l.segments).filter(|s| side(s, gutter)).collect();
+        let edge = segments.iter().map(|s| s.x_min).fold(f64::INFINITY, f64::min);
+        let aligned = segments.iter().filter(|s| s.x_min - edge <= s.size).count();
+        segments.len() >= 2 && aligned * 2 >= segments.len()
+    };
+    if !column(|s, g| s.x_max <= g) || !column(|s, g| s.x_min >= g) {
+        return None;
+    }
+
+    Some(gutter)
+}
+
+// Emits the page in reading order: lines that span the gutter are flushed as
+// they are, and each block of lines between them is read left column first.
+fn read_columns(lines: &[Line], gutter: f64) -> String {
+    let mut out: Vec<String> = Vec::new();
+    let mut left: Vec<String> = Vec::new();
+    let mut right: Vec<String> = Vec::new();
+
+    let flush = |out: &mut Vec<String>, left: &mut Vec<String>, right: &mut Vec<String>| {
+        out.append(left);
+        if !right.is_empty() {
+            out.push(String::new());
+            out.append(right);
+        }
+    };
+
+    for line in lines {
+        let spans = line.segments.iter().any(|s| s.x_min < gutter && s.x_max > gutter);
+        if spans {
+            flush(&mut out, &mut left, &mut right);
+            out.push(line_text(line));
+            continue;
+        }
+
+        let (l, r): (Vec<&Segment>, Vec<&Segment>) = line.segments.iter().partition(|s| s.x_max <= gutter);
+        if !l.is_empty() {
+            left.push(l.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join("  "));
+        }
+        if !r.is_empty() {
+            right.push(r.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join("  "));
+        }
+    }
+    flush(&mut out, &mut left, &mut right);
+
+    out.join("\n")
+}
+
+// Typographic ligatures are decoded as single code points by most fonts;
+// expand them so keyword matching sees plain letters.
+fn normalize_ligatures(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\u{FB00}' => out.push_str("ff"),
+            '\u{FB01}' => out.push_str("fi"),
+            '\u{FB02}' => out.push_str("fl"),
+            '\u{FB03}' => out.push_str("ffi"),
+            '\u{FB04}' => out.push_str("ffl"),
+            '\u{FB05}' | '\u{FB06}' => out.push_str("st"),
+            '\u{00A0}' => out.push(' '),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: f64 = 10.0;
+    const ADVANCE: f64 = 5.0;
+
+    // One glyph per character, as pdf-extract reports them; spaces are
+    // dropped by the layout but still advance the pen.
+    fn words(x: f64, y: f64, text: &str) -> Vec<Glyph> {
+        text.chars()
+            .enumerate()
+            .map(|(i, c)| Glyph {
+                x: x + i as f64 * ADVANCE,
+                y,
+                width: ADVANCE,
+                size: SIZE,
+                text: normalize_ligatures(&c.to_string()),
+            })
+            .collect()
+    }
+
+    fn page(runs: &[(f64, f64, &str)]) -> String {
+        layout_page(runs.iter().flat_map(|&(x, y, text)| words(x, y, text)).collect())
+    }
+
+    #[test]
+    fn single_column_reads_top_to_bottom() {
+        let text = page(&[
+            (50.0, 100.0, "Experience"),
+            (50.0, 130.0, "Led a team of five"),
+            (50.0, 115.0, "Backend Engineer at Acme"),
+            (60.0, 145.0, "Cut costs by 30%"),
+        ]);
+        assert_eq!(text, "Experience\nBackend Engineer at Acme\nLed a team of five\nCut costs by 30%");
+    }
+
+    #[test]
+    fn sidebar_is_read_column_by_column() {
+        let text = page(&[
+            (40.0, 80.0, "Jane Doe, Backend Engineer, jane@example.com, London"),
+            (40.0, 100.0, "Skills"),
+            (250.0, 100.0, "Experience"),
+            (40.0, 115.0, "Rust"),
+            (250.0, 115.0, "Built the billing API"),
+            (40.0, 130.0, "Python"),
+            (250.0, 130.0, "Led a team of five"),
+            (40.0, 145.0, "SQL"),
+            (250.0, 145.0, "Shipped weekly"),
+        ]);
+        assert_eq!(
+            text,
+            "Jane Doe, Backend Engineer, jane@example.com, London\n\
+             Skills\nRust\nPython\nSQL\n\n\
+             Experience\nBuilt the billing API\nLed a team of five\nShipped weekly"
+        );
+    }
+
+    #[test]
+    fn right_aligned_dates_are_not_a_column() {
+        // Each date ends at x = 500, so their left edges are ragged
+        fn dated<'a>(y: f64, title: &'a str, date: &'a str) -> [(f64, f64, &'a str); 2] {
+            [(50.0, y, title), (500.0 - date.len() as f64 * ADVANCE, y, date)]
+        }
+        let runs: Vec<(f64, f64, &str)> = [
+            dated(100.0, "Senior Engineer, Acme", "2019 - 2023"),
+            dated(115.0, "Engineer, Initech", "Jan 2018 - Dec 2019"),
+            dated(130.0, "Junior Engineer, Hooli", "2015 - present"),
+            dated(145.0, "Intern, Globex", "May 2012 - 2015"),
+        ]
+        .concat();
+        let lines: Vec<Line> = {
+            let glyphs: Vec<Glyph> = runs.iter().flat_map(|&(x, y, text)| words(x, y, text)).collect();
+            let mut rows: Vec<Vec<Glyph>> = Vec::new();
+            for glyph in glyphs {
+                match rows.last_mut() {
+                    Some(row) if row[0].y == glyph.y => row.push(glyph),
+                    _ => rows.push(vec![glyph]),
+                }
+            }
+            rows.into_iter().map(build_line).collect()
+        };
+        assert!(find_gutter(&lines).is_none());
+
+        assert_eq!(
+            page(&runs),
+            "Senior Engineer, Acme  2019 - 2023\n\
+             Engineer, Initech  Jan 2018 - Dec 2019\n\
+             Junior Engineer, Hooli  2015 - present\n\
+             Intern, Globex  May 2012 - 2015"
+        );
+    }
+
+    #[test]
+    fn ligatures_are_expanded() {
+        assert_eq!(normalize_ligatures("\u{FB01}nance \u{FB04}ow\u{00A0}e\u{FB00}ort"), "finance fflow effort");
+        // The expanded letters stay one glyph wide in the layout
+        assert_eq!(page(&[(50.0, 100.0, "Pro\u{FB01}le")]), "Profile");
+    }
+
+    // A one-page PDF showing `text` in Helvetica, with `trailer` added to its
+    // trailer dictionary and `extra` appended as further objects.
+    fn pdf(text: &str, trailer: &str, extra: &[&str]) -> Vec<u8> {
+        let content = format!("BT /F1 12 Tf 72 720 Td ({}) Tj ET", text);
+        let mut objects = vec![
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Resources << /Font << /F1 4 0 R >> >> /Contents 5 0 R >>"
+                .to_string(),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_string(),
+            format!("<< /Length {} >>\nstream\n{}\nendstream", content.len(), content),
+        ];
+        objects.extend(extra.iter().map(|o| o.to_string()));
+
+        let mut out = b"%PDF-1.4\n".to_vec();
+        let mut offsets = Vec::new();
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(out.len());
+            out.extend(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).bytes());
+        }
+        let xref = out.len();
+        out.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).bytes());
+        for offset in offsets {
+            out.extend(format!("{:010} 00000 n \n", offset).bytes());
+        }
+        out.extend(
+            format!("trailer\n<< /Size {} /Root 1 0 R {} >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, trailer, xref).bytes(),
+        );
+        out
+    }
+
+    #[test]
+    fn extracts_text() {
+        let text = extract_text(&pdf("Backend engineer with ten years of experience", "", &[])).unwrap();
+        assert_eq!(text, "Backend engineer with ten years of experience");
+    }
+
+    #[test]
+    fn near_empty_pdfs_are_image_only() {
+        assert!(matches!(extract_text(&pdf("Page 1", "", &[])), Err(PdfError::ImageOnly)));
+    }
+
+    #[test]
+    fn password_protected_pdfs_are_encrypted() {
+        // Standard security handler whose user password isn't empty
+        let hash = "<".to_string() + &"5a".repeat(32) + ">";
+        let encrypt = format!("<< /Filter /Standard /V 1 /R 2 /O {} /U {} /P -4 >>", hash, hash);
+        let id = "<00112233445566778899aabbccddeeff>";
+        let data = pdf("Backend engineer with ten years of experience", &format!("/Encrypt 6 0 R /ID [{} {}]", id, id), &[&encrypt]);
+        assert!(matches!(extract_text(&data), Err(PdfError::Encrypted)));
+    }
+
+    #[test]
+    fn garbage_is_malformed() {
+        assert!(matches!(extract_text(b"%PDF-1.4 not really"), Err(PdfError::Malformed(_))));
+    }
+}
@@ -0,0 +1,288 @@
+use futures_util::{stream, Stream};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgListener;
+use sqlx::PgPool;
+use std::convert::Infallible;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+const CHANNEL: &str = "critique_progress";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Uploaded,
+    TextExtracted,
+    SentToAi,
+    ScoresReceived,
+    Saved,
+    Failed,
+}
+
+impl Stage {
+    // Successful stages in the order a job moves through them.
+    const ORDER: [Stage; 5] = [Stage::Uploaded, Stage::TextExtracted, Stage::SentToAi, Stage::ScoresReceived, Stage::Saved];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Stage::Uploaded => "uploaded",
+            Stage::TextExtracted => "text_extracted",
+            Stage::SentToAi => "sent_to_ai",
+            Stage::ScoresReceived => "scores_received",
+            Stage::Saved => "saved",
+            Stage::Failed => "failed",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Stage> {
+        Stage::ORDER.into_iter().chain([Stage::Failed]).find(|stage| stage.as_str() == s)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgressEvent {
+    pub job_id: Uuid,
+    pub stage: Stage,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
+impl ProgressEvent {
+    pub fn new(job_id: Uuid, stage: Stage) -> Self {
+        Self { job_id, stage, data: None }
+    }
+
+    pub fn with_data(mut self, data: serde_json::Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    // A failure that will be retried does not end the stream.
+    fn is_final(&self) -> bool {
+        match self.stage {
+            Stage::Saved => true,
+            Stage::Failed => !self.data.as_ref().and_then(|d| d["will_retry"].as_bool()).unwrap_or(false),
+            _ => false,
+        }
+    }
+}
+
+// Records the job's stage and broadcasts the event through Postgres, so
+// clients connected to any backend instance see progress from any worker.
+pub async fn publish(db_pool: &PgPool, job_id: i32, event: &ProgressEvent) -> Result<(), sqlx::Error> {
+    if event.stage != Stage::Failed {
+        sqlx::query!(
+            "UPDATE critique_jobs SET stage = $2 WHERE id = $1",
+            job_id,
+            event.stage.as_str()
+        )
+        .execute(db_pool)
+        .await?;
+    }
+
+    let payload = serde_json::to_string(event).unwrap_or_default();
+    sqlx::query!("SELECT pg_notify($1, $2)", CHANNEL, payload)
+        .execute(db_pool)
+        .await?;
+
+    Ok(())
+}
+
+// Fans progress notifications from Postgres out to this instance's SSE
+// subscribers.
+pub struct ProgressHub {
+    sender: broadcast::Sender<ProgressEvent>,
+}
+
+impl ProgressHub {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(256);
+        Self { sender }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ProgressEvent> {
+        self.sender.subscribe()
+    }
+}
+
+pub fn spawn_listener(db_pool: Arc<PgPool>, hub: Arc<ProgressHub>) {
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = listen(&db_pool, &hub).await {
+                eprintln!("Progress listener error: {}", e);
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        }
+    });
+}
+
+async fn listen(db_pool: &PgPool, hub: &ProgressHub) -> Result<(), sqlx::Error> {
+    let mut listener = PgListener::connect_with(db_pool).await?;
+    listener.listen(CHANNEL).await?;
+
+    loop {
+        let notification = listener.recv().await?;
+        match serde_json::from_str::<ProgressEvent>(notification.payload()) {
+            // No subscribers is not an error
+            Ok(event) => {
+                let _ = hub.sender.send(event);
+            }
+            Err(e) => eprintln!("Invalid progress notification: {}", e),
+        }
+    }
+}
+
+// The state of a job when a client starts watching it.
+pub struct Snapshot {
+    pub job_id: Uuid,
+    pub status: String,
+    pub stage: String,
+    pub critique_id: Option<Uuid>,
+    pub error: Option<String>,
+    pub error_code: Option<String>,
+}
+
+impl Snapshot {
+    // The event that ended the job, once it has finished for good.
+    fn final_event(&self) -> Option<ProgressEvent> {
+        match self.status.as_str() {
+            "failed" => Some(ProgressEvent::new(self.job_id, Stage::Failed).with_data(serde_json::json!({
+                "error": self.error,
+                "code": self.error_code,
+                "will_retry": false,
+            }))),
+            "succeeded" => Some(ProgressEvent::new(self.job_id, Stage::Saved).with_data(serde_json::json!({
+                "critique_id": self.critique_id,
+            }))),
+            _ => None,
+        }
+    }
+}
+
+async fn load_snapshot(db_pool: &PgPool, job_id: Uuid) -> Result<Snapshot, sqlx::Error> {
+    sqlx::query_as!(
+        Snapshot,
+        r#"
+        SELECT j.public_id AS job_id, j.status, j.stage, j.error, j.error_code,
+               c.public_id AS "critique_id?"
+        FROM critique_jobs j
+        LEFT JOIN critiques c ON j.critique_id = c.id
+        WHERE j.public_id = $1
+        "#,
+        job_id
+    )
+    .fetch_one(db_pool)
+    .await
+}
+
+// Replays the stages the job has already passed, then follows live events
+// until it is saved or fails for good. The receiver must be subscribed before
+// the snapshot is read so no event falls in between. If the receiver falls
+// behind and drops events, the job is read again so a missed final event
+// still ends the stream.
+pub fn event_stream(
+    db_pool: Arc<PgPool>,
+    snapshot: Snapshot,
+    receiver: broadcast::Receiver<ProgressEvent>,
+) -> impl Stream<Item = Result<warp::sse::Event, Infallible>> {
+    let job_id = snapshot.job_id;
+    let mut replay: Vec<ProgressEvent> = Vec::new();
+    let mut finished = true;
+
+    match snapshot.status.as_str() {
+        "failed" => {
+            replay.extend(Stage::ORDER[..2].iter().map(|&stage| ProgressEvent::new(job_id, stage)));
+            replay.extend(snapshot.final_event());
+        }
+        "succeeded" => {
+            replay.extend(Stage::ORDER[..4].iter().map(|&stage| ProgressEvent::new(job_id, stage)));
+            replay.extend(snapshot.final_event());
+        }
+        _ => {
+            let current = Stage::parse(&snapshot.stage).unwrap_or(Stage::TextExtracted);
+            let reached = Stage::ORDER.iter().position(|&s| s == current).unwrap_or(1);
+            replay.extend(Stage::ORDER[..=reached].iter().map(|&stage| ProgressEvent::new(job_id, stage)));
+            finished = false;
+        }
+    }
+
+    let replayed = stream::iter(replay.into_iter().map(to_sse));
+
+    let live = stream::unfold((receiver, finished), move |(mut receiver, finished)| {
+        let db_pool = db_pool.clone();
+        async move {
+            if finished {
+                return None;
+            }
+            loop {
+                match receiver.recv().await {
+                    Ok(event) if event.job_id == job_id => {
+                        let finished = event.is_final();
+                        return Some((to_sse(event), (receiver, finished)));
+                    }
+                    Ok(_) => continue,
+                    Err(broadcast::error::RecvError::Lagged(_)) => match load_snapshot(&db_pool, job_id).await {
+                        Ok(snapshot) => match snapshot.final_event() {
+                            Some(event) => return Some((to_sse(event), (receiver, true))),
+                            None => continue,
+                        },
+                        Err(e) => {
+                            eprintln!("Database error: {}", e);
+                            return None;
+                        }
+                    },
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        }
+    });
+
+    futures_util::StreamExt::chain(replayed, live)
+}
+
+fn to_sse(event: ProgressEvent) -> Result<warp::sse::Event, Infallible> {
+    let sse = warp::sse::Event::default().event(event.stage.as_str());
+    Ok(sse.json_data(&event).unwrap_or_else(|_| warp::sse::Event::default().event(event.stage.as_str())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_db;
+    use futures_util::StreamExt;
+
+    #[sqlx::test(migrations = false)]
+    async fn lagging_subscriber_still_sees_the_job_finish(db_pool: PgPool) {
+        test_db::schema(&db_pool).await;
+        let resume_id = test_db::resume(&db_pool, "Resume").await;
+        let job_id = sqlx::query_scalar!(
+            "INSERT INTO critique_jobs (resume_id, status, stage) VALUES ($1, 'running', 'sent_to_ai') RETURNING public_id",
+            resume_id
+        )
+        .fetch_one(&db_pool)
+        .await
+        .unwrap();
+
+        let (sender, receiver) = broadcast::channel(2);
+        let snapshot = load_snapshot(&db_pool, job_id).await.unwrap();
+        let events = event_stream(Arc::new(db_pool.clone()), snapshot, receiver);
+
+        // The job finishes while the subscriber is flooded with other jobs'
+        // events, so its own saved event is among those dropped
+        sqlx::query!("UPDATE critique_jobs SET status = 'succeeded' WHERE public_id = $1", job_id)
+            .execute(&db_pool)
+            .await
+            .unwrap();
+        sender.send(ProgressEvent::new(job_id, Stage::Saved)).unwrap();
+        for _ in 0..4 {
+            sender.send(ProgressEvent::new(Uuid::new_v4(), Stage::SentToAi)).unwrap();
+        }
+
+        let events: Vec<_> = tokio::time::timeout(std::time::Duration::from_secs(5), events.collect())
+            .await
+            .expect("stream did not end");
+        assert_eq!(events.len(), 4);
+        assert!(format!("{:?}", events.last()).contains("saved"));
+    }
+}
@@ -0,0 +1,231 @@
+use regex::{Captures, Regex};
+use serde_json::Value;
+use std::sync::LazyLock;
+
+// Detectors run in this order over text already redacted by the earlier
+// ones, so a link's email or an ID number's digits aren't matched twice.
+static EMAIL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\b[a-z0-9._%+-]+@[a-z0-9-]+(?:\.[a-z0-9-]+)*\.[a-z]{2,}\b").unwrap());
+static LINK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?i)\b(?:https?://|www\.|(?:linkedin|github|gitlab|twitter|x|behance|dribbble)\.com/)[^\s<>()\[\]"']*[^\s<>()\[\]"'.,;:!?]"#,
+    )
+    .unwrap()
+});
+// US social security and similar national ID formats.
+static ID_NUMBER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b\d{3}-\d{2}-\d{4}\b").unwrap());
+// Digit groups with separators; PHONE_DIGITS tells phone numbers apart from
+// date ranges and figures.
+static PHONE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:\+\d{1,3}[\s.-]?)?(?:\(\d{1,4}\)[\s.-]?)?\b\d{2,4}(?:[\s.-]\d{2,4}){1,4}\b|\+\d{9,15}\b").unwrap()
+});
+const PHONE_DIGITS: std::ops::RangeInclusive<usize> = 9..=15;
+// A house number and street, with an optional unit, city, state and ZIP.
+static ADDRESS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r"\b\d{1,6}\s+(?:[A-Z][A-Za-z.'-]*\s+){1,4}",
+        r"(?:Street|St|Avenue|Ave|Road|Rd|Boulevard|Blvd|Lane|Ln|Drive|Dr|Court|Ct|Way|Place|Pl|",
+        r"Terrace|Parkway|Pkwy|Circle|Cir|Square|Sq|Highway|Hwy)\b\.?",
+        r"(?:,?\s*(?:Apt|Apartment|Suite|Ste|Unit|#)\.?\s*[A-Za-z0-9-]+)?",
+        r"(?:,\s*[A-Z][A-Za-z .'-]*[A-Za-z])?",
+        r"(?:,?\s*[A-Z]{2}\s+\d{5}(?:-\d{4})?)?",
+    ))
+    .unwrap()
+});
+static NAME_LINE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\p{Lu}[\p{L}.'-]*(?:\s+\p{Lu}[\p{L}.'-]*){1,3}$").unwrap());
+// A first line with any of these words is a heading or a job title, such as
+// "Professional Summary" or "Senior Software Engineer", not a name.
+const NOT_A_NAME: &[&str] = &[
+    "resume", "résumé", "curriculum", "vitae", "cv", "summary", "professional", "profile", "objective",
+    "experience", "employment", "work", "history", "education", "skills", "projects", "certifications",
+    "references", "contact", "information", "details", "senior", "junior", "lead", "principal", "staff",
+    "chief", "head", "engineer", "engineering", "developer", "programmer", "manager", "director", "analyst",
+    "designer", "consultant", "architect", "scientist", "specialist", "intern", "officer", "administrator",
+    "coordinator", "assistant", "associate", "executive", "president", "software", "data", "product",
+    "marketing", "sales", "technical", "full", "stack", "frontend", "backend",
+];
+
+// Replaces a resume's personal details with placeholders such as [EMAIL_1]
+// before it goes to the AI service, and maps them back in what comes back.
+// The same value always gets the same placeholder.
+pub struct Redactor {
+    name: Option<Regex>,
+    // (placeholder, original)
+    replacements: Vec<(String, String)>,
+}
+
+impl Redactor {
+    // The candidate's name is taken from the first line of the resume when
+    // it looks like one.
+    pub fn for_resume(text: &str) -> Self {
+        let name = text
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .filter(|line| line.len() <= 60 && NAME_LINE.is_match(line))
+            .filter(|line| {
+                !line
+                    .split_whitespace()
+                    .any(|word| NOT_A_NAME.contains(&word.to_lowercase().as_str()))
+            })
+            .map(|line| {
+                // Matches the name however it is separated, as in file names
+                let parts: Vec<String> = line.split_whitespace().map(regex::escape).collect();
+                Regex::new(&format!("(?i){}", parts.join(r"[\s_.-]+"))).unwrap()
+            });
+        Self {
+            name,
+            replacements: Vec::new(),
+        }
+    }
+
+    pub fn redact(&mut self, text: &str) -> String {
+        let mut text = self.replace(&EMAIL, "EMAIL", text, |_| true);
+        text = self.replace(&LINK, "LINK", &text, |_| true);
+        text = self.replace(&ID_NUMBER, "ID", &text, |_| true);
+        text = self.replace(&PHONE, "PHONE", &text, |m| {
+            PHONE_DIGITS.contains(&m.chars().filter(char::is_ascii_digit).count())
+        });
+        text = self.replace(&ADDRESS, "ADDRESS", &text, |_| true);
+        if let Some(name) = self.name.clone() {
+            // `\b` would miss the name in Jane_Doe_resume.pdf, as '_' is a
+            // word character, so only letters and digits count as adjoining
+            let source = text;
+            text = name
+                .replace_all(&source, |caps: &Captures| {
+                    let found = caps.get(0).unwrap();
+                    let before = source[..found.start()].chars().next_back();
+                    let after = source[found.end()..].chars().next();
+                    if [before, after].iter().flatten().any(|c| c.is_alphanumeric()) {
+                        found.as_str().to_string()
+                    } else {
+                        self.placeholder("NAME", found.as_str())
+                    }
+                })
+                .into_owned();
+        }
+        text
+    }
+
+    pub fn restore(&self, text: &str) -> String {
+        self.replacements
+            .iter()
+            .fold(text.to_string(), |text, (placeholder, original)| text.replace(placeholder, original))
+    }
+
+    // Restores every string in a JSON value, such as a critique's feedback.
+    pub fn restore_value(&self, value: &mut Value) {
+        match value {
+            Value::String(s) => *s = self.restore(s),
+            Value::Array(items) => items.iter_mut().for_each(|item| self.restore_value(item)),
+            Value::Object(fields) => fields.values_mut().for_each(|field| self.restore_value(field)),
+            _ => {}
+        }
+    }
+
+    fn replace(&mut self, pattern: &Regex, kind: &str, text: &str, accept: impl Fn(&str) -> bool) -> String {
+        pattern
+            .replace_all(text, |caps: &Captures| {
+                let found = &caps[0];
+                if accept(found) {
+                    self.placeholder(kind, found)
+                } else {
+                    found.to_string()
+                }
+            })
+            .into_owned()
+    }
+
+    fn placeholder(&mut self, kind: &str, original: &str) -> String {
+        // Names differ in case between the heading and elsewhere; the
+        // heading's form is restored for all of them
+        let existing = self.replacements.iter().find(|(placeholder, known)| {
+            placeholder.starts_with(&format!("[{}", kind)) && (known == original || kind == "NAME")
+        });
+        if let Some((placeholder, _)) = existing {
+            return placeholder.clone();
+        }
+
+        let placeholder = if kind == "NAME" {
+            "[NAME]".to_string()
+        } else {
+            let n = self
+                .replacements
+                .iter()
+                .filter(|(placeholder, _)| placeholder.starts_with(&format!("[{}_", kind)))
+                .count();
+            format!("[{}_{}]", kind, n + 1)
+        };
+        self.replacements.push((placeholder.clone(), original.to_string()));
+        placeholder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const RESUME: &str = "Jane Doe\n\
+        jane.doe@example.com | +1 (555) 123-4567 | linkedin.com/in/janedoe\n\
+        12 Oak Street, Springfield, IL 62704\n\
+        SSN 123-45-6789\n\n\
+        Backend engineer, 2015 - 2019 and 2019-2023. Cut latency 40% across 1,200 services.\n\
+        Contact Jane Doe at jane.doe@example.com.";
+
+    #[test]
+    fn redacts_personal_details() {
+        let mut redactor = Redactor::for_resume(RESUME);
+        let redacted = redactor.redact(RESUME);
+
+        for detail in ["Jane", "Doe", "jane.doe@example.com", "555", "janedoe", "Oak Street", "123-45-6789"] {
+            assert!(!redacted.contains(detail), "{} left in {}", detail, redacted);
+        }
+        assert!(redacted.starts_with("[NAME]\n[EMAIL_1] | [PHONE_1] | [LINK_1]\n[ADDRESS_1]\nSSN [ID_1]"));
+        // Dates and figures aren't phone numbers
+        assert!(redacted.contains("2015 - 2019 and 2019-2023"));
+        assert!(redacted.contains("40% across 1,200 services"));
+        // A repeated value keeps its placeholder
+        assert!(redacted.ends_with("Contact [NAME] at [EMAIL_1]."));
+    }
+
+    #[test]
+    fn redacts_the_name_in_the_filename() {
+        let mut redactor = Redactor::for_resume(RESUME);
+        assert_eq!(redactor.redact("Jane_Doe_Resume.pdf"), "[NAME]_Resume.pdf");
+        assert_eq!(redactor.redact("janedoe-cv.pdf"), "janedoe-cv.pdf");
+    }
+
+    #[test]
+    fn headings_and_titles_are_not_names() {
+        for first_line in ["Senior Software Engineer", "Professional Summary", "Curriculum Vitae", "Work Experience"] {
+            let text = format!("{}\nBuilt things at Acme", first_line);
+            let mut redactor = Redactor::for_resume(&text);
+            assert_eq!(redactor.redact(&text), text);
+        }
+    }
+
+    #[test]
+    fn restores_placeholders() {
+        let mut redactor = Redactor::for_resume(RESUME);
+        redactor.redact(RESUME);
+
+        assert_eq!(redactor.restore("Write to [EMAIL_1], [NAME]."), "Write to jane.doe@example.com, Jane Doe.");
+
+        let mut feedback = json!({
+            "contact": "[PHONE_1] is formatted well",
+            "suggestions": ["Shorten [LINK_1]", {"note": "[ADDRESS_1]"}],
+            "score": 4.5,
+        });
+        redactor.restore_value(&mut feedback);
+        assert_eq!(
+            feedback,
+            json!({
+                "contact": "+1 (555) 123-4567 is formatted well",
+                "suggestions": ["Shorten linkedin.com/in/janedoe", {"note": "12 Oak Street, Springfield, IL 62704"}],
+                "score": 4.5,
+            })
+        );
+    }
+}
@@ -0,0 +1,145 @@
+use anyhow::{bail, Context, Result};
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::crypto::Keyring;
+use crate::storage::{self, PendingObject, Storage};
+use crate::upload;
+
+const BATCH_SIZE: i64 = 100;
+
+struct StoredResume {
+    id: i32,
+    storage_key: Option<String>,
+    original_content: String,
+    data_key: Option<Vec<u8>>,
+    data_key_id: Option<String>,
+}
+
+// Moves every resume onto the active master key: data keys wrapped by an
+// older key are rewrapped, which leaves files and text untouched, and
+// resumes stored before encryption was enabled are sealed. Once it reports
+// no failures, retired keys can be dropped from ENCRYPTION_KEYS. Safe to run
+// while the server is up and to run again after an interruption.
+pub async fn run(db_pool: &PgPool, config: &Config, storage: Arc<dyn Storage>, keyring: &Keyring) -> Result<()> {
+    let Some(active_key_id) = keyring.active_key_id() else {
+        bail!("ENCRYPTION_KEYS must be set to re-encrypt");
+    };
+
+    let (mut rewrapped, mut sealed, mut failed) = (0, 0, 0);
+    let mut last_id = 0;
+    loop {
+        let batch = sqlx::query_as!(
+            StoredResume,
+            r#"
+            SELECT id, storage_key, original_content, data_key, data_key_id
+            FROM resumes
+            WHERE id > $1 AND data_key_id IS DISTINCT FROM $2
+            ORDER BY id
+            LIMIT $3
+            "#,
+            last_id,
+            active_key_id,
+            BATCH_SIZE
+        )
+        .fetch_all(db_pool)
+        .await?;
+        let Some(last) = batch.last() else {
+            break;
+        };
+        last_id = last.id;
+
+        for resume in batch {
+            let result = match (&resume.data_key_id, &resume.data_key) {
+                (Some(key_id), Some(wrapped)) => rewrap(db_pool, keyring, resume.id, key_id, wrapped).await,
+                _ => seal(db_pool, config, storage.clone(), keyring, &resume).await,
+            };
+            match result {
+                Ok(()) if resume.data_key.is_some() => rewrapped += 1,
+                Ok(()) => sealed += 1,
+                Err(e) => {
+                    eprintln!("Resume {}: {:#}", resume.id, e);
+                    failed += 1;
+                }
+            }
+        }
+    }
+
+    println!(
+        "Re-encryption under key '{}': {} rewrapped, {} sealed, {} failed",
+        active_key_id, rewrapped, sealed, failed
+    );
+    if failed > 0 {
+        bail!("{} resumes could not be re-encrypted", failed);
+    }
+    Ok(())
+}
+
+async fn rewrap(db_pool: &PgPool, keyring: &Keyring, id: i32, key_id: &str, wrapped: &[u8]) -> Result<()> {
+    let key = keyring.data_key(key_id, wrapped)?;
+    let key = keyring.rotate(&key).context("no active key")?;
+
+    // Skipped if the row changed since it was read; the next run picks it up
+    sqlx::query!(
+        "UPDATE resumes SET data_key = $1, data_key_id = $2 WHERE id = $3 AND data_key_id = $4",
+        key.wrapped(),
+        key.key_id(),
+        id,
+        key_id
+    )
+    .execute(db_pool)
+    .await?;
+    Ok(())
+}
+
+// Seals a plaintext resume. Its file is written sealed under a new key and
+// the old one removed only after the row points at the new one.
+async fn seal(
+    db_pool: &PgPool,
+    config: &Config,
+    storage: Arc<dyn Storage>,
+    keyring: &Keyring,
+    resume: &StoredResume,
+) -> Result<()> {
+    let key = keyring.new_data_key().context("no active key")?;
+
+    let stored = match &resume.storage_key {
+        Some(storage_key) => {
+            let blob = storage
+                .get(storage_key)
+                .await
+                .with_context(|| format!("reading {}", storage_key))?;
+            let staged = upload::stage(blob.stream, &config.upload_dir, u64::MAX, Some(key.sealer())).await?;
+            Some(PendingObject::put(storage.clone(), storage::object_key(Uuid::new_v4()), staged).await?)
+        }
+        None => None,
+    };
+
+    let updated = sqlx::query!(
+        r#"
+        UPDATE resumes
+        SET original_content = $1, storage_key = $2, data_key = $3, data_key_id = $4
+        WHERE id = $5 AND data_key IS NULL
+        "#,
+        key.seal_text(&resume.original_content),
+        stored.as_ref().map(PendingObject::key),
+        key.wrapped(),
+        key.key_id(),
+        resume.id
+    )
+    .execute(db_pool)
+    .await?
+    .rows_affected();
+
+    if updated == 1 {
+        if let Some(stored) = stored {
+            stored.keep();
+        }
+        if let Some(old_key) = &resume.storage_key {
+            storage.delete(old_key).await.with_context(|| format!("removing plaintext {}", old_key))?;
+        }
+    }
+    Ok(())
+}
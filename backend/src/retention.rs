@@ -0,0 +1,129 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::storage::Storage;
+
+const BATCH_SIZE: i64 = 100;
+
+struct ExpiredResume {
+    id: i32,
+    public_id: Uuid,
+    user_id: i32,
+    storage_key: Option<String>,
+    uploaded_at: DateTime<Utc>,
+    critiques: i64,
+}
+
+// Whether any retention window is set; with none the purge never runs.
+pub fn enabled(config: &Config) -> bool {
+    config.retention_days > 0 || config.retention_days_by_role.iter().any(|(_, days)| *days > 0)
+}
+
+pub fn spawn_purger(db_pool: Arc<PgPool>, config: Arc<Config>, storage: Arc<dyn Storage>) {
+    if !enabled(&config) {
+        return;
+    }
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = purge(&db_pool, &config, &*storage, config.retention_dry_run).await {
+                eprintln!("Retention purge error: {}", e);
+            }
+            tokio::time::sleep(Duration::from_secs(config.retention_interval_secs)).await;
+        }
+    });
+}
+
+// Deletes resumes older than their owner's retention window, with their
+// critiques, jobs and history entries (by cascade) and their stored files.
+// Resumes with a critique still queued or running are left for the next run.
+// A dry run deletes each batch in a transaction that is rolled back, so it
+// reports exactly what a real run would remove and touches no files.
+pub async fn purge(db_pool: &PgPool, config: &Config, storage: &dyn Storage, dry_run: bool) -> Result<(), sqlx::Error> {
+    let (roles, days): (Vec<String>, Vec<i32>) = config
+        .retention_days_by_role
+        .iter()
+        .map(|(role, days)| (role.clone(), *days as i32))
+        .unzip();
+    let verb = if dry_run { "would remove" } else { "removed" };
+
+    let (mut resumes, mut critiques) = (0, 0);
+    let mut last_id = 0;
+    loop {
+        let mut tx = db_pool.begin().await?;
+        let batch = sqlx::query_as!(
+            ExpiredResume,
+            r#"
+            WITH expired AS (
+                SELECT r.id
+                FROM resumes r
+                JOIN users u ON u.id = r.user_id
+                LEFT JOIN UNNEST($1::text[], $2::int[]) AS by_role(role, days) ON by_role.role = u.role
+                WHERE r.id > $3
+                  AND COALESCE(by_role.days, $4) > 0
+                  AND r.uploaded_at < NOW() - make_interval(days => COALESCE(by_role.days, $4))
+                  AND NOT EXISTS (
+                      SELECT 1 FROM critique_jobs j
+                      WHERE j.resume_id = r.id AND j.status IN ('queued', 'running')
+                  )
+                ORDER BY r.id
+                LIMIT $5
+            )
+            DELETE FROM resumes r
+            USING expired
+            WHERE r.id = expired.id
+            RETURNING r.id, r.public_id, r.user_id as "user_id!", r.storage_key, r.uploaded_at as "uploaded_at!",
+                      (SELECT COUNT(*) FROM critiques c WHERE c.resume_id = r.id) as "critiques!"
+            "#,
+            &roles,
+            &days,
+            last_id,
+            config.retention_days as i32,
+            BATCH_SIZE
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        let Some(last) = batch.iter().map(|resume| resume.id).max() else {
+            break;
+        };
+        last_id = last;
+
+        if dry_run {
+            tx.rollback().await?;
+        } else {
+            tx.commit().await?;
+        }
+
+        for resume in batch {
+            println!(
+                "Retention: {} resume {} of user {} uploaded {} with {} critiques",
+                verb,
+                resume.public_id,
+                resume.user_id,
+                resume.uploaded_at.format("%Y-%m-%d"),
+                resume.critiques
+            );
+            resumes += 1;
+            critiques += resume.critiques;
+
+            if dry_run {
+                continue;
+            }
+            // As with a user's own delete, the rows are gone, so a file that
+            // can't be removed is only logged
+            if let Some(key) = &resume.storage_key {
+                if let Err(e) = storage.delete(key).await {
+                    eprintln!("Failed to remove resume file {}: {}", key, e);
+                }
+            }
+        }
+    }
+
+    if resumes > 0 || dry_run {
+        println!("Retention: {} {} resumes and {} critiques", verb, resumes, critiques);
+    }
+    Ok(())
+}
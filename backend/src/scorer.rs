@@ -0,0 +1,198 @@
+use async_trait::async_trait;
+use serde_json::json;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::ai_client::{AiClient, AiError};
+use crate::config::Config;
+use crate::models::{AiCritiqueRequest, AiCritiqueResponse};
+use crate::redact::Redactor;
+use crate::scoring;
+use crate::validation::{self, CATEGORIES};
+
+// A critique together with the provider that produced it. `generated_by` is
+// stored with the critique.
+pub struct Scored {
+    pub critique: AiCritiqueResponse,
+    pub generated_by: &'static str,
+    // Set when a fallback provider stood in for the configured one.
+    pub fallback_reason: Option<&'static str>,
+    // Corrections made to the critique, and why a fallback was needed.
+    pub issues: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct ScoreError {
+    pub code: &'static str,
+    pub message: String,
+    // Set when the request was never sent (the AI circuit breaker is open)
+    // and is worth retrying after this long.
+    pub retry_after: Option<Duration>,
+}
+
+impl fmt::Display for ScoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ScoreError {}
+
+impl From<AiError> for ScoreError {
+    fn from(e: AiError) -> Self {
+        let retry_after = match e {
+            AiError::CircuitOpen { retry_in } => Some(retry_in),
+            _ => None,
+        };
+        Self {
+            code: e.code(),
+            message: e.to_string(),
+            retry_after,
+        }
+    }
+}
+
+#[async_trait]
+pub trait Scorer: Send + Sync {
+    async fn score(&self, request: &AiCritiqueRequest) -> Result<Scored, ScoreError>;
+}
+
+// Builds the provider chain named by SCORER and SCORER_FALLBACK.
+pub fn from_config(config: &Config) -> Arc<dyn Scorer> {
+    let primary = provider(&config.scorer, config);
+    match config.scorer_fallback.as_deref() {
+        None => primary,
+        Some(name) => Arc::new(FallbackScorer {
+            primary,
+            fallback: provider(name, config),
+        }),
+    }
+}
+
+fn provider(name: &str, config: &Config) -> Arc<dyn Scorer> {
+    match name {
+        "ai" => Arc::new(RemoteScorer {
+            client: AiClient::new(config),
+            invalid_retries: config.ai_invalid_retries,
+        }),
+        "heuristic" => Arc::new(HeuristicScorer),
+        "mock" => Arc::new(MockScorer),
+        other => panic!("Unknown scorer '{}'; expected ai, heuristic or mock", other),
+    }
+}
+
+// The Python AI service. Personal details are redacted from what it is sent
+// and restored in the critique it returns. Its output is validated before
+// use, and a critique that fails validation is requested again up to
+// `invalid_retries` times; model output varies between calls, so a retry
+// often succeeds.
+pub struct RemoteScorer {
+    client: AiClient,
+    invalid_retries: u32,
+}
+
+#[async_trait]
+impl Scorer for RemoteScorer {
+    async fn score(&self, request: &AiCritiqueRequest) -> Result<Scored, ScoreError> {
+        let mut redactor = Redactor::for_resume(&request.resume_text);
+        let request = AiCritiqueRequest {
+            resume_text: redactor.redact(&request.resume_text),
+            filename: redactor.redact(&request.filename),
+            ..request.clone()
+        };
+
+        let mut attempt = 0;
+        loop {
+            let raw = self.client.critique(&request).await?;
+            match validation::validate(&raw) {
+                Ok(validated) => {
+                    let mut critique = validated.critique;
+                    redactor.restore_value(&mut critique.detailed_feedback);
+                    redactor.restore_value(&mut critique.improvement_suggestions);
+                    return Ok(Scored {
+                        critique,
+                        generated_by: "ai",
+                        fallback_reason: None,
+                        issues: validated.issues,
+                    })
+                }
+                Err(reasons) => {
+                    let error = ScoreError {
+                        code: "ai_invalid_critique",
+                        message: format!("AI critique failed validation: {}", reasons.join("; ")),
+                        retry_after: None,
+                    };
+                    if attempt >= self.invalid_retries {
+                        return Err(error);
+                    }
+                    eprintln!("{} (attempt {})", error, attempt + 1);
+                    attempt += 1;
+                }
+            }
+        }
+    }
+}
+
+// The backend's built-in deterministic engine; see `scoring`.
+pub struct HeuristicScorer;
+
+#[async_trait]
+impl Scorer for HeuristicScorer {
+    async fn score(&self, request: &AiCritiqueRequest) -> Result<Scored, ScoreError> {
+        Ok(Scored {
+            critique: scoring::score(&request.resume_text),
+            generated_by: "heuristic",
+            fallback_reason: None,
+            issues: Vec::new(),
+        })
+    }
+}
+
+// Returns the same critique for every resume, for running the backend
+// without the AI service in tests and demos.
+pub struct MockScorer;
+
+#[async_trait]
+impl Scorer for MockScorer {
+    async fn score(&self, _request: &AiCritiqueRequest) -> Result<Scored, ScoreError> {
+        Ok(Scored {
+            critique: AiCritiqueResponse {
+                overall_score: 3.0,
+                structure_score: 3.0,
+                keywords_score: 3.0,
+                action_verbs_score: 3.0,
+                quantified_impact_score: 3.0,
+                readability_score: 3.0,
+                detailed_feedback: CATEGORIES.iter().map(|c| (c.to_string(), json!("Mock feedback."))).collect(),
+                improvement_suggestions: CATEGORIES.iter().map(|c| (c.to_string(), json!(["Mock suggestion."]))).collect(),
+            },
+            generated_by: "mock",
+            fallback_reason: None,
+            issues: Vec::new(),
+        })
+    }
+}
+
+// Uses `fallback` whenever `primary` fails, so a resume is never left without
+// a critique because one provider is down.
+pub struct FallbackScorer {
+    primary: Arc<dyn Scorer>,
+    fallback: Arc<dyn Scorer>,
+}
+
+#[async_trait]
+impl Scorer for FallbackScorer {
+    async fn score(&self, request: &AiCritiqueRequest) -> Result<Scored, ScoreError> {
+        match self.primary.score(request).await {
+            Ok(scored) => Ok(scored),
+            Err(e) => {
+                eprintln!("Scorer failed, using fallback: {}", e);
+                let mut scored = self.fallback.score(request).await?;
+                scored.fallback_reason = Some(e.code);
+                scored.issues.push(e.message);
+                Ok(scored)
+            }
+        }
+    }
+}
@@ -0,0 +1,422 @@
+use serde_json::json;
+use std::collections::HashSet;
+
+use crate::models::AiCritiqueResponse;
+
+// Deterministic, offline scoring used when the AI service can't produce a
+// critique. It looks only at surface features of the text, so its scores are
+// coarser than the model's, but the same resume always gets the same result.
+
+const STRONG_VERBS: &[&str] = &[
+    "accelerated", "achieved", "analyzed", "architected", "automated", "built", "coached", "collaborated",
+    "conducted", "consolidated", "created", "cut", "decreased", "delivered", "deployed", "designed",
+    "developed", "directed", "drove", "eliminated", "engineered", "established", "executed", "expanded",
+    "generated", "grew", "headed", "implemented", "improved", "increased", "initiated", "integrated",
+    "introduced", "launched", "led", "managed", "mentored", "migrated", "modernized", "negotiated",
+    "optimized", "orchestrated", "organized", "oversaw", "pioneered", "planned", "produced", "published",
+    "raised", "rebuilt", "redesigned", "reduced", "refactored", "resolved", "restructured", "revamped",
+    "saved", "scaled", "secured", "shipped", "simplified", "spearheaded", "standardized", "streamlined",
+    "strengthened", "supervised", "trained", "transformed", "tripled", "doubled", "won", "wrote",
+];
+
+const WEAK_PHRASES: &[&str] = &[
+    "responsible for", "duties included", "worked on", "helped with", "helped to", "assisted with",
+    "tasked with", "involved in", "participated in",
+];
+
+// Broad enough to register in most technical and business resumes; this is
+// a proxy for keyword density, not a match against a specific role.
+pub const KEYWORDS: &[&str] = &[
+    "agile", "scrum", "kanban", "jira", "sql", "postgresql", "mysql", "mongodb", "redis", "python", "java",
+    "javascript", "typescript", "rust", "go", "c++", "c#", "ruby", "php", "kotlin", "swift", "react",
+    "angular", "vue", "node.js", "django", "flask", "spring", "aws", "azure", "gcp", "docker",
+    "kubernetes", "terraform", "ci/cd", "git", "linux", "rest", "graphql", "microservices", "api",
+    "machine learning", "data analysis", "tableau", "power bi", "excel", "salesforce", "sap", "crm",
+    "erp", "seo", "sem", "google analytics", "a/b testing", "roadmap", "stakeholder", "budget",
+    "forecasting", "compliance", "risk management", "project management", "product management",
+    "b2b", "saas", "kpi", "okr", "p&l", "supply chain", "six sigma", "lean", "figma", "ux", "ui",
+    "security", "testing", "tdd", "devops", "etl", "spark", "hadoop", "pandas", "tensorflow", "pytorch",
+];
+
+const SECTIONS: &[(&str, &[&str])] = &[
+    ("summary", &["summary", "profile", "objective", "about me"]),
+    ("experience", &["experience", "employment", "work history", "professional background"]),
+    ("education", &["education", "academic", "qualifications"]),
+    ("skills", &["skills", "technical skills", "competencies", "technologies"]),
+];
+
+const FIRST_PERSON: &[&str] = &["i", "me", "my", "mine", "myself"];
+
+struct Analysis<'a> {
+    word_count: usize,
+    sections: Vec<&'static str>,
+    has_contact: bool,
+    statements: Vec<&'a str>,
+    bulleted: bool,
+}
+
+pub fn score(resume_text: &str) -> AiCritiqueResponse {
+    let analysis = analyze(resume_text);
+    let lower = resume_text.to_lowercase();
+
+    let (structure_score, structure_feedback, structure_tips) = score_structure(&analysis);
+    let (keywords_score, keywords_feedback, keywords_tips) = score_keywords(&lower);
+    let (action_verbs_score, action_verbs_feedback, action_verbs_tips) = score_action_verbs(&analysis, &lower);
+    let (quantified_impact_score, quantified_impact_feedback, quantified_impact_tips) = score_quantified_impact(&analysis);
+    let (readability_score, readability_feedback, readability_tips) = score_readability(&analysis);
+
+    let overall_score = round1(
+        (structure_score + keywords_score + action_verbs_score + quantified_impact_score + readability_score) / 5.0,
+    );
+
+    AiCritiqueResponse {
+        overall_score,
+        structure_score,
+        keywords_score,
+        action_verbs_score,
+        quantified_impact_score,
+        readability_score,
+        detailed_feedback: json!({
+            "structure": structure_feedback,
+            "keywords": keywords_feedback,
+            "action_verbs": action_verbs_feedback,
+            "quantified_impact": quantified_impact_feedback,
+            "readability": readability_feedback,
+        }),
+        improvement_suggestions: json!({
+            "structure": structure_tips,
+            "keywords": keywords_tips,
+            "action_verbs": action_verbs_tips,
+            "quantified_impact": quantified_impact_tips,
+            "readability": readability_tips,
+        }),
+    }
+}
+
+fn analyze(text: &str) -> Analysis<'_> {
+    let lines: Vec<&str> = text.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+
+    let mut sections = Vec::new();
+    for line in &lines {
+        // Headings are short lines, often upper case or followed by a colon.
+        let heading = line.trim_end_matches(':').to_lowercase();
+        if heading.split_whitespace().count() > 4 {
+            continue;
+        }
+        for (name, aliases) in SECTIONS {
+            if !sections.contains(name) && aliases.iter().any(|a| heading.contains(a)) {
+                sections.push(*name);
+            }
+        }
+    }
+
+    let has_contact = text.contains('@') || lines.iter().take(8).any(|l| l.chars().filter(char::is_ascii_digit).count() >= 7);
+
+    let bullets: Vec<&str> = lines.iter().filter_map(|l| strip_bullet(l)).collect();
+    let bulleted = bullets.len() >= 3;
+    // Without bullets, fall back to any line long enough to be a statement.
+    let statements = if bulleted {
+        bullets
+    } else {
+        lines.iter().copied().filter(|l| l.split_whitespace().count() >= 5).collect()
+    };
+
+    Analysis {
+        word_count: text.split_whitespace().count(),
+        sections,
+        has_contact,
+        statements,
+        bulleted,
+    }
+}
+
+fn strip_bullet(line: &str) -> Option<&str> {
+    let rest = line.trim_start_matches(['•', '-', '*', '▪', '◦', '–', '‣', '·']);
+    if rest.len() < line.len() {
+        return Some(rest.trim_start());
+    }
+
+    // Numbered items such as "1." or "2)"
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    if digits > 0 && digits < 3 && matches!(line[digits..].chars().next(), Some('.') | Some(')')) {
+        return Some(line[digits + 1..].trim_start());
+    }
+    None
+}
+
+fn score_structure(a: &Analysis) -> (f32, String, Vec<String>) {
+    let mut score = 1.0;
+    let mut tips = Vec::new();
+
+    score += 0.5 * a.sections.len() as f32;
+    for (name, _) in SECTIONS {
+        if !a.sections.contains(name) {
+            tips.push(format!("Add a clearly labelled {} section", name));
+        }
+    }
+
+    if a.has_contact {
+        score += 0.5;
+    } else {
+        tips.push("Include an email address and phone number at the top".to_string());
+    }
+
+    if a.bulleted {
+        score += 0.5;
+    } else {
+        tips.push("Present experience as bullet points rather than paragraphs".to_string());
+    }
+
+    match a.word_count {
+        300..=900 => score += 1.0,
+        150..=299 | 901..=1200 => score += 0.5,
+        n if n < 150 => tips.push("Expand the resume; it is too short to show your experience".to_string()),
+        _ => tips.push("Trim the resume to one or two pages".to_string()),
+    }
+
+    let feedback = format!(
+        "Found {} of {} standard sections{}, {} words{}.",
+        a.sections.len(),
+        SECTIONS.len(),
+        if a.sections.is_empty() { String::new() } else { format!(" ({})", a.sections.join(", ")) },
+        a.word_count,
+        if a.bulleted { ", using bullet points" } else { ", with few or no bullet points" },
+    );
+
+    (clamp(score), feedback, tips)
+}
+
+fn score_keywords(lower: &str) -> (f32, String, Vec<String>) {
+    let words: HashSet<&str> = lower
+        .split(|c: char| !(c.is_alphanumeric() || "+#./&".contains(c)))
+        .map(|w| w.trim_end_matches('.'))
+        .collect();
+    let found: Vec<&str> = KEYWORDS
+        .iter()
+        .copied()
+        .filter(|k| if k.contains(' ') { lower.contains(k) } else { words.contains(k) })
+        .collect();
+
+    let score = 1.0 + found.len() as f32 * 0.3;
+    let mut tips = Vec::new();
+    if found.len() < 10 {
+        tips.push("Mirror the skills and tools named in the job descriptions you target".to_string());
+        tips.push("Add a skills section listing technologies and methodologies by name".to_string());
+    }
+
+    let feedback = if found.is_empty() {
+        "No common industry or technical keywords were detected.".to_string()
+    } else {
+        format!("Detected {} industry keywords, including {}.", found.len(), found.iter().take(6).copied().collect::<Vec<_>>().join(", "))
+    };
+
+    (clamp(score), feedback, tips)
+}
+
+fn score_action_verbs(a: &Analysis, lower: &str) -> (f32, String, Vec<String>) {
+    let mut strong = 0;
+    let mut distinct = HashSet::new();
+    for statement in &a.statements {
+        let first = statement.split_whitespace().next().unwrap_or("").to_lowercase();
+        let first = first.trim_matches(|c: char| !c.is_alphabetic());
+        if STRONG_VERBS.contains(&first) {
+            strong += 1;
+            distinct.insert(first.to_string());
+        }
+    }
+    let weak = WEAK_PHRASES.iter().map(|p| lower.matches(p).count()).sum::<usize>();
+
+    let ratio = if a.statements.is_empty() { 0.0 } else { strong as f32 / a.statements.len() as f32 };
+    let score = 1.0 + ratio * 2.5 + (distinct.len() as f32 * 0.15).min(1.5) - (weak as f32 * 0.25).min(1.0);
+
+    let mut tips = Vec::new();
+    if ratio < 0.6 {
+        tips.push("Start each bullet with a strong past-tense verb such as led, built or reduced".to_string());
+    }
+    if weak > 0 {
+        tips.push("Replace phrases like \"responsible for\" and \"worked on\" with what you achieved".to_string());
+    }
+    if distinct.len() < 6 && strong > 0 {
+        tips.push("Vary your verbs instead of repeating the same few".to_string());
+    }
+
+    let feedback = format!(
+        "{} of {} statements open with a strong action verb ({} distinct); {} weak phrases found.",
+        strong,
+        a.statements.len(),
+        distinct.len(),
+        weak
+    );
+
+    (clamp(score), feedback, tips)
+}
+
+fn score_quantified_impact(a: &Analysis) -> (f32, String, Vec<String>) {
+    let quantified = a.statements.iter().filter(|s| has_metric(s)).count();
+    let ratio = if a.statements.is_empty() { 0.0 } else { quantified as f32 / a.statements.len() as f32 };
+
+    // Around half of bullets carrying a number reads as strongly results-driven.
+    let score = 1.0 + (ratio / 0.5).min(1.0) * 4.0;
+
+    let mut tips = Vec::new();
+    if ratio < 0.5 {
+        tips.push("Add numbers to your achievements: percentages, revenue, time saved or team size".to_string());
+        tips.push("State the outcome of each project, not only the task".to_string());
+    }
+
+    let feedback = format!("{} of {} statements include a measurable result.", quantified, a.statements.len());
+
+    (clamp(score), feedback, tips)
+}
+
+// True if the text has a figure other than a bare year, which in resumes is
+// almost always a date rather than an achievement.
+fn has_metric(text: &str) -> bool {
+    let mut found = false;
+    for token in text.split_whitespace() {
+        let trimmed = token.trim_matches(|c: char| !c.is_alphanumeric() && c != '%' && c != '$');
+        let digits: String = trimmed.chars().filter(char::is_ascii_digit).collect();
+        if digits.is_empty() {
+            continue;
+        }
+        let is_year = digits.len() == 4 && trimmed.len() == 4 && (digits.starts_with("19") || digits.starts_with("20"));
+        if !is_year || trimmed.contains(['%', '$']) {
+            found = true;
+        }
+    }
+    found
+}
+
+fn score_readability(a: &Analysis) -> (f32, String, Vec<String>) {
+    let lengths: Vec<usize> = a.statements.iter().map(|s| s.split_whitespace().count()).collect();
+    let mut tips = Vec::new();
+
+    if lengths.is_empty() {
+        tips.push("Break the resume into short, scannable statements".to_string());
+        return (1.0, "No distinct statements could be identified.".to_string(), tips);
+    }
+
+    let average = lengths.iter().sum::<usize>() as f32 / lengths.len() as f32;
+    let long = lengths.iter().filter(|&&n| n > 35).count();
+    let first_person = a
+        .statements
+        .iter()
+        .flat_map(|s| s.split_whitespace())
+        .filter(|w| FIRST_PERSON.contains(&w.trim_matches(|c: char| !c.is_alphabetic()).to_lowercase().as_str()))
+        .count();
+
+    let mut score = 5.0;
+    if !(8.0..=25.0).contains(&average) {
+        score -= 1.0;
+        tips.push(if average > 25.0 {
+            "Shorten bullets to one or two lines each".to_string()
+        } else {
+            "Give each bullet enough detail to stand on its own".to_string()
+        });
+    }
+    score -= (long as f32 * 0.5).min(1.5);
+    if long > 0 {
+        tips.push("Split run-on statements into separate bullets".to_string());
+    }
+    score -= (first_person as f32 * 0.25).min(1.0);
+    if first_person > 0 {
+        tips.push("Drop first-person pronouns; resumes are written in implied first person".to_string());
+    }
+
+    let feedback = format!(
+        "Statements average {:.0} words; {} are overly long and {} first-person pronouns were found.",
+        average, long, first_person
+    );
+
+    (clamp(score), feedback, tips)
+}
+
+fn clamp(score: f32) -> f32 {
+    round1(score.clamp(1.0, 5.0))
+}
+
+fn round1(value: f32) -> f32 {
+    (value * 10.0).round() / 10.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAIN: &str = "\
+Jane Doe
+jane@example.com
+
+Experience
+Backend Engineer, Acme
+- Responsible for the billing system and its database
+- Worked on the team that looked after the public website
+- Helped with support tickets from customers when needed
+
+Education
+BSc Computer Science";
+
+    const STRONG: &str = "\
+Jane Doe
+jane@example.com
+
+Experience
+Backend Engineer, Acme
+- Led a team of 5 engineers rebuilding billing in Rust and PostgreSQL
+- Reduced page load time by 40% by caching API responses in Redis
+- Automated deployments with Docker and Kubernetes, saving $20k a year
+- Migrated 3 services to AWS with zero downtime
+
+Education
+BSc Computer Science";
+
+    fn scores(critique: &AiCritiqueResponse) -> [f32; 6] {
+        [
+            critique.overall_score,
+            critique.structure_score,
+            critique.keywords_score,
+            critique.action_verbs_score,
+            critique.quantified_impact_score,
+            critique.readability_score,
+        ]
+    }
+
+    #[test]
+    fn same_text_same_scores() {
+        for text in [PLAIN, STRONG] {
+            let first = score(text);
+            let second = score(text);
+            assert_eq!(scores(&first), scores(&second));
+            assert_eq!(first.detailed_feedback, second.detailed_feedback);
+            assert_eq!(first.improvement_suggestions, second.improvement_suggestions);
+        }
+    }
+
+    #[test]
+    fn scores_stay_in_range() {
+        let long = "word ".repeat(5000);
+        let verbs = STRONG_VERBS.iter().map(|v| format!("- {} 100% of {}", v, KEYWORDS.join(" "))).collect::<Vec<_>>().join("\n");
+        for text in ["", "   \n\n", "I", PLAIN, STRONG, long.as_str(), verbs.as_str()] {
+            for value in scores(&score(text)) {
+                assert!((0.0..=5.0).contains(&value), "{} out of range for {:?}", value, text);
+            }
+        }
+    }
+
+    #[test]
+    fn action_verbs_and_numbers_score_higher() {
+        let plain = score(PLAIN);
+        let strong = score(STRONG);
+        assert!(strong.action_verbs_score > plain.action_verbs_score);
+        assert!(strong.quantified_impact_score > plain.quantified_impact_score);
+        assert!(strong.keywords_score > plain.keywords_score);
+        assert!(strong.overall_score > plain.overall_score);
+    }
+
+    #[test]
+    fn years_are_not_metrics() {
+        assert!(!has_metric("Backend Engineer, 2019 - 2023"));
+        assert!(has_metric("Cut costs by 15%"));
+        assert!(has_metric("Raised $2000 in 2021"));
+    }
+}
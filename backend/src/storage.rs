@@ -0,0 +1,422 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::Utc;
+use futures_util::stream::{BoxStream, StreamExt, TryStreamExt};
+use hmac::{Hmac, Mac};
+use reqwest::{Method, StatusCode, Url};
+use sha2::{Digest, Sha256};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use unicode_normalization::UnicodeNormalization;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::upload::StagedFile;
+
+// Longest display name kept, in bytes; most filesystems allow no more.
+const MAX_NAME_BYTES: usize = 255;
+// Extensions up to this long survive truncation of a long name.
+const MAX_EXTENSION_BYTES: usize = 16;
+const DEFAULT_NAME: &str = "resume";
+
+// Turns a client-supplied filename into a name that is only ever shown to
+// the user: directory parts, control characters and direction overrides are
+// dropped, whitespace is collapsed and the result is NFC and bounded in
+// length. It is never used to build a path.
+pub fn display_name(raw: &str) -> String {
+    // Some browsers send the full path from the client's machine
+    let base = raw.rsplit(['/', '\\']).next().unwrap_or_default();
+    let cleaned: String = base
+        .nfc()
+        .filter(|c| !c.is_control() && !matches!(c, '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}'))
+        .collect();
+    let collapsed = cleaned.split_whitespace().collect::<Vec<_>>().join(" ");
+    let name = collapsed.trim_start_matches('.').trim_start();
+
+    if name.is_empty() {
+        return DEFAULT_NAME.to_string();
+    }
+    truncate(name)
+}
+
+fn truncate(name: &str) -> String {
+    if name.len() <= MAX_NAME_BYTES {
+        return name.to_string();
+    }
+    let (stem, extension) = match name.rfind('.') {
+        Some(dot) if dot > 0 && name.len() - dot <= MAX_EXTENSION_BYTES => name.split_at(dot),
+        _ => (name, ""),
+    };
+    let mut end = MAX_NAME_BYTES - extension.len();
+    while !stem.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}{}", stem[..end].trim_end(), extension)
+}
+
+// The key a new upload is stored under: ab/cd/<id>, sharded on the id so no
+// directory grows unbounded. Only the server-generated id is used.
+pub fn object_key(id: Uuid) -> String {
+    let id = id.simple().to_string();
+    format!("{}/{}/{}", &id[..2], &id[2..4], id)
+}
+
+// A stored file's contents, streamed.
+pub struct Blob {
+    pub size: Option<u64>,
+    pub stream: BoxStream<'static, io::Result<Bytes>>,
+}
+
+// Where uploaded files are kept. Keys are relative, '/'-separated and
+// chosen by the server; the same key works with every backend.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    // Stores an upload under `key`, consuming the staged copy.
+    async fn put(&self, key: &str, file: StagedFile) -> io::Result<()>;
+    // Fails with ErrorKind::NotFound when there is no such object.
+    async fn get(&self, key: &str) -> io::Result<Blob>;
+    // Deleting a missing object is not an error.
+    async fn delete(&self, key: &str) -> io::Result<()>;
+}
+
+// Builds the backend named by STORAGE.
+pub fn from_config(config: &Config) -> Arc<dyn Storage> {
+    match config.storage.as_str() {
+        "local" => Arc::new(LocalStorage {
+            root: PathBuf::from(&config.upload_dir),
+        }),
+        "s3" => Arc::new(S3Storage::new(config)),
+        other => panic!("Unknown storage '{}'; expected local or s3", other),
+    }
+}
+
+// An object written during a request that the database doesn't refer to
+// yet. Dropped without `keep`, it is deleted again.
+pub struct PendingObject {
+    storage: Arc<dyn Storage>,
+    key: String,
+    kept: bool,
+}
+
+impl PendingObject {
+    pub async fn put(storage: Arc<dyn Storage>, key: String, file: StagedFile) -> io::Result<Self> {
+        storage.put(&key, file).await?;
+        Ok(Self {
+            storage,
+            key,
+            kept: false,
+        })
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    // Called once the database refers to the object.
+    pub fn keep(mut self) {
+        self.kept = true;
+    }
+}
+
+impl Drop for PendingObject {
+    fn drop(&mut self) {
+        if self.kept {
+            return;
+        }
+        let storage = self.storage.clone();
+        let key = std::mem::take(&mut self.key);
+        tokio::spawn(async move {
+            if let Err(e) = storage.delete(&key).await {
+                eprintln!("Failed to remove {}: {}", key, e);
+            }
+        });
+    }
+}
+
+// Files under UPLOAD_DIR on this machine.
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    // Keys are never built from client input, but one that would leave the
+    // root is refused all the same.
+    fn path(&self, key: &str) -> io::Result<PathBuf> {
+        let relative = Path::new(key);
+        if key.is_empty() || !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid storage key {:?}", key)));
+        }
+        Ok(self.root.join(relative))
+    }
+}
+
+#[async_trait]
+impl Storage for LocalStorage {
+    // Staged uploads live under the same root, so this is a rename.
+    async fn put(&self, key: &str, mut file: StagedFile) -> io::Result<()> {
+        file.persist(&self.path(key)?).await?;
+        file.keep();
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> io::Result<Blob> {
+        let file = tokio::fs::File::open(self.path(key)?).await?;
+        let size = file.metadata().await?.len();
+        Ok(Blob {
+            size: Some(size),
+            stream: tokio_util::io::ReaderStream::new(file).boxed(),
+        })
+    }
+
+    async fn delete(&self, key: &str) -> io::Result<()> {
+        match tokio::fs::remove_file(self.path(key)?).await {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+const SIGNED_HEADERS: &str = "host;x-amz-content-sha256;x-amz-date";
+// sha256 of an empty body
+const EMPTY_SHA256: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+// An S3-compatible bucket (AWS, MinIO, R2, ...), addressed path-style as
+// <endpoint>/<bucket>/<key> and signed with AWS Signature Version 4.
+pub struct S3Storage {
+    http: reqwest::Client,
+    endpoint: String,
+    bucket: String,
+    region: String,
+    access_key_id: String,
+    secret_access_key: String,
+}
+
+impl S3Storage {
+    fn new(config: &Config) -> Self {
+        let required = |value: &str, name: &str| {
+            if value.is_empty() {
+                panic!("{} must be set when STORAGE=s3", name);
+            }
+            value.to_string()
+        };
+        Self {
+            http: reqwest::Client::new(),
+            endpoint: required(&config.s3_endpoint, "S3_ENDPOINT").trim_end_matches('/').to_string(),
+            bucket: required(&config.s3_bucket, "S3_BUCKET"),
+            region: config.s3_region.clone(),
+            access_key_id: required(&config.s3_access_key_id, "S3_ACCESS_KEY_ID"),
+            secret_access_key: required(&config.s3_secret_access_key, "S3_SECRET_ACCESS_KEY"),
+        }
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        key: &str,
+        payload_hash: &str,
+        body: Option<(reqwest::Body, u64)>,
+    ) -> io::Result<reqwest::Response> {
+        let url = self.object_url(key)?;
+        let amz_date = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let authorization = self.authorization(&method, &url, payload_hash, &amz_date);
+
+        let mut request = self
+            .http
+            .request(method.clone(), url)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("x-amz-date", &amz_date)
+            .header(reqwest::header::AUTHORIZATION, authorization);
+        if let Some((body, length)) = body {
+            request = request.header(reqwest::header::CONTENT_LENGTH, length).body(body);
+        }
+
+        let response = request.send().await.map_err(io::Error::other)?;
+        match response.status() {
+            status if status.is_success() => Ok(response),
+            StatusCode::NOT_FOUND => Err(io::ErrorKind::NotFound.into()),
+            status => Err(io::Error::other(format!("S3 {} {} returned {}", method, key, status))),
+        }
+    }
+
+    // Path-style, so any S3-compatible endpoint works without DNS per bucket
+    fn object_url(&self, key: &str) -> io::Result<Url> {
+        Url::parse(&format!("{}/{}/{}", self.endpoint, self.bucket, encode_key(key)))
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+    }
+
+    fn authorization(&self, method: &Method, url: &Url, payload_hash: &str, amz_date: &str) -> String {
+        let host = match url.port() {
+            Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+        let canonical_request = format!(
+            "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method,
+            url.path(),
+            host,
+            payload_hash,
+            amz_date,
+            SIGNED_HEADERS,
+            payload_hash
+        );
+
+        let date = &amz_date[..8];
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{:x}",
+            amz_date,
+            scope,
+            Sha256::digest(canonical_request.as_bytes())
+        );
+        let signing_key = [date, &self.region, "s3", "aws4_request"]
+            .iter()
+            .fold(format!("AWS4{}", self.secret_access_key).into_bytes(), |key, part| {
+                hmac_sha256(&key, part.as_bytes())
+            });
+
+        let signature: String = hmac_sha256(&signing_key, string_to_sign.as_bytes())
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key_id, scope, SIGNED_HEADERS, signature
+        )
+    }
+}
+
+#[async_trait]
+impl Storage for S3Storage {
+    async fn put(&self, key: &str, file: StagedFile) -> io::Result<()> {
+        let body = tokio::fs::File::open(file.path()).await?;
+        let body = reqwest::Body::wrap_stream(tokio_util::io::ReaderStream::new(body));
+        // The staged copy is removed when `file` drops
+        self.send(Method::PUT, key, file.stored_hash(), Some((body, file.stored_len()))).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> io::Result<Blob> {
+        let response = self.send(Method::GET, key, EMPTY_SHA256, None).await?;
+        Ok(Blob {
+            size: response.content_length(),
+            stream: response.bytes_stream().map_err(io::Error::other).boxed(),
+        })
+    }
+
+    async fn delete(&self, key: &str) -> io::Result<()> {
+        match self.send(Method::DELETE, key, EMPTY_SHA256, None).await {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+// Percent-encodes everything but unreserved characters and the '/'
+// separators, as the SigV4 canonical URI for S3 expects.
+fn encode_key(key: &str) -> String {
+    key.bytes()
+        .map(|b| {
+            if b.is_ascii_alphanumeric() || b"-._~/".contains(&b) {
+                (b as char).to_string()
+            } else {
+                format!("%{:02X}", b)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn s3() -> S3Storage {
+        S3Storage {
+            http: reqwest::Client::new(),
+            endpoint: "http://minio.local:9000".to_string(),
+            bucket: "resumes".to_string(),
+            region: "eu-west-2".to_string(),
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+        }
+    }
+
+    // Expected signatures computed independently with botocore's S3SigV4Auth
+    #[test]
+    fn signs_requests_as_aws_does() {
+        let storage = s3();
+        let url = storage.object_url("resumes/ab/Jane Doe+CV (1).pdf").unwrap();
+        assert_eq!(url.as_str(), "http://minio.local:9000/resumes/resumes/ab/Jane%20Doe%2BCV%20%281%29.pdf");
+
+        let put_hash = format!("{:x}", Sha256::digest(b"resume body"));
+        assert_eq!(
+            storage.authorization(&Method::PUT, &url, &put_hash, "20240115T093000Z"),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20240115/eu-west-2/s3/aws4_request, \
+             SignedHeaders=host;x-amz-content-sha256;x-amz-date, \
+             Signature=5a63eb50be5a9d0ad741ad071f8b5413e8246bf55bd224b36c080371d5a4020d"
+        );
+        assert_eq!(
+            storage.authorization(&Method::GET, &url, EMPTY_SHA256, "20240115T093000Z"),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20240115/eu-west-2/s3/aws4_request, \
+             SignedHeaders=host;x-amz-content-sha256;x-amz-date, \
+             Signature=0b9f756eefa20ec3a0702f04dcb98a1afa05ebde325463a239bf5c9a277e107b"
+        );
+    }
+
+    #[test]
+    fn display_names_drop_directories() {
+        assert_eq!(display_name("../../etc/passwd"), "passwd");
+        assert_eq!(display_name("/home/jane/CV.pdf"), "CV.pdf");
+        assert_eq!(display_name(r"C:\Users\Jane\Documents\CV final.docx"), "CV final.docx");
+        assert_eq!(display_name("uploads/.."), "resume");
+        assert_eq!(display_name("../"), "resume");
+        assert_eq!(display_name(".env"), "env");
+    }
+
+    #[test]
+    fn display_names_drop_control_characters() {
+        assert_eq!(display_name("CV\0.pdf"), "CV.pdf");
+        assert_eq!(display_name("Jane\r\nDoe\t CV.pdf"), "JaneDoe CV.pdf");
+        // A right-to-left override would show "fdp.exe" as "exe.pdf"
+        assert_eq!(display_name("CV\u{202E}fdp.exe"), "CVfdp.exe");
+        assert_eq!(display_name("\u{7}\u{1b}"), "resume");
+        // Decomposed "é" is stored composed
+        assert_eq!(display_name("Rene\u{301}.pdf"), "Ren\u{e9}.pdf");
+    }
+
+    #[test]
+    fn long_names_are_cut_on_a_char_boundary() {
+        let name = format!("{}.pdf", "é".repeat(300));
+        let shown = display_name(&name);
+        assert!(shown.len() <= MAX_NAME_BYTES);
+        // Fits resumes.filename, a VARCHAR(255)
+        assert!(shown.chars().count() <= 255);
+        assert!(shown.ends_with(".pdf"));
+        assert_eq!(shown.trim_end_matches(".pdf").chars().filter(|&c| c != 'é').count(), 0);
+
+        // No extension: the whole name is cut
+        let shown = display_name(&"日本".repeat(150));
+        assert_eq!(shown.len(), MAX_NAME_BYTES);
+        assert_eq!(shown, "日本".repeat(42) + "日");
+    }
+
+    #[test]
+    fn object_keys_are_sharded_on_the_id() {
+        let id = Uuid::parse_str("3f2504e0-4f89-41d3-9a0c-0305e82c3301").unwrap();
+        assert_eq!(object_key(id), "3f/25/3f2504e04f8941d39a0c0305e82c3301");
+
+        let key = object_key(Uuid::new_v4());
+        let parts: Vec<&str> = key.split('/').collect();
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts[0], &parts[2][..2]);
+        assert_eq!(parts[1], &parts[2][2..4]);
+        assert!(parts[2].len() == 32 && parts[2].bytes().all(|b| b.is_ascii_hexdigit()));
+    }
+}
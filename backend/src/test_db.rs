@@ -0,0 +1,73 @@
+// Setup for tests that need Postgres. Each #[sqlx::test] runs against a
+// fresh database created from DATABASE_URL; these give it the schema and
+// the rows a job needs.
+use sqlx::{Executor, PgPool};
+
+use crate::config::Config;
+
+pub async fn schema(db_pool: &PgPool) {
+    db_pool.execute(include_str!("../../database/init.sql")).await.unwrap();
+    sqlx::migrate!("./migrations").run(db_pool).await.unwrap();
+}
+
+// A user with one uploaded resume; returns the resume's id.
+pub async fn resume(db_pool: &PgPool, text: &str) -> i32 {
+    let user_id = sqlx::query_scalar!(
+        "INSERT INTO users (email, password_hash, name) VALUES ('candidate@example.com', 'x', 'Candidate') RETURNING id"
+    )
+    .fetch_one(db_pool)
+    .await
+    .unwrap();
+    let resume_id = sqlx::query_scalar!(
+        "INSERT INTO resumes (user_id, filename, original_content) VALUES ($1, 'resume.txt', $2) RETURNING id",
+        user_id,
+        text
+    )
+    .fetch_one(db_pool)
+    .await
+    .unwrap();
+    sqlx::query!(
+        "INSERT INTO feedback_history (user_id, resume_id, version_number, lineage_id) VALUES ($1, $2, 1, gen_random_uuid())",
+        user_id,
+        resume_id
+    )
+    .execute(db_pool)
+    .await
+    .unwrap();
+    resume_id
+}
+
+// The defaults from Config::from_env, with the mock scorer and no fallback,
+// encryption or retention.
+pub fn config() -> Config {
+    Config {
+        database_url: String::new(),
+        jwt_secret: "secret".to_string(),
+        ai_service_url: "http://localhost:8001".to_string(),
+        upload_dir: std::env::temp_dir().to_string_lossy().into_owned(),
+        max_file_size: 10 * 1024 * 1024,
+        max_concurrent_extractions: 4,
+        job_workers: 1,
+        job_max_attempts: 3,
+        ai_timeout_secs: 60,
+        ai_max_retries: 2,
+        ai_breaker_threshold: 5,
+        ai_breaker_cooldown_secs: 30,
+        ai_invalid_retries: 1,
+        scorer: "mock".to_string(),
+        scorer_fallback: None,
+        ai_models: Vec::new(),
+        rubric_version: "1".to_string(),
+        storage: "local".to_string(),
+        s3_endpoint: String::new(),
+        s3_bucket: String::new(),
+        s3_region: "us-east-1".to_string(),
+        s3_access_key_id: String::new(),
+        s3_secret_access_key: String::new(),
+        encryption_keys: Vec::new(),
+        retention_days: 0,
+        retention_days_by_role: Vec::new(),
+        retention_interval_secs: 3600,
+        retention_dry_run: false,
+    }
+}
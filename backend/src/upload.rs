@@ -0,0 +1,279 @@
+use bytes::Buf;
+use futures_util::{Stream, TryStreamExt};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+use uuid::Uuid;
+
+use crate::crypto::{DataKey, Opener, Sealer};
+
+// How much of an upload is read to tell what kind of file it is.
+pub const HEAD_LEN: u64 = 8 * 1024;
+// Chunk size when reading a staged file back.
+const READ_CHUNK: usize = 64 * 1024;
+
+#[derive(Debug)]
+pub enum UploadError {
+    TooLarge { limit: u64 },
+    Stream(Box<dyn std::error::Error + Send + Sync>),
+    Io(io::Error),
+}
+
+impl fmt::Display for UploadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UploadError::TooLarge { limit } => write!(f, "File is larger than the {} byte limit", limit),
+            UploadError::Stream(e) => write!(f, "Upload interrupted: {}", e),
+            UploadError::Io(e) => write!(f, "Could not store upload: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for UploadError {}
+
+impl From<io::Error> for UploadError {
+    fn from(e: io::Error) -> Self {
+        UploadError::Io(e)
+    }
+}
+
+// An uploaded file on disk. Until `keep` is called the file is deleted when
+// this is dropped, so every early return from the upload handler cleans up
+// after itself.
+#[derive(Debug)]
+pub struct StagedFile {
+    path: PathBuf,
+    size: u64,
+    hash: String,
+    stored_len: u64,
+    stored_hash: String,
+    kept: bool,
+}
+
+impl StagedFile {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Size of the uploaded content.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    // Hex sha256 of the uploaded content.
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    // Length and hex sha256 of the bytes on disk, which differ from the
+    // content's when it was sealed.
+    pub fn stored_len(&self) -> u64 {
+        self.stored_len
+    }
+
+    pub fn stored_hash(&self) -> &str {
+        &self.stored_hash
+    }
+
+    // The first HEAD_LEN bytes of the uploaded content, opened with `key` if
+    // it was sealed. Only as much of the file is read as that takes.
+    pub async fn head(&self, key: Option<&DataKey>) -> io::Result<Vec<u8>> {
+        let path = self.path.clone();
+        let opener = key.map(DataKey::opener);
+        tokio::task::spawn_blocking(move || {
+            let mut head = Vec::new();
+            ContentReader::open(&path, opener)?.take(HEAD_LEN).read_to_end(&mut head)?;
+            Ok(head)
+        })
+        .await?
+    }
+
+    // Renames the file to its final name. Both live under the upload
+    // directory, so readers see either no file or the whole of it.
+    pub async fn persist(&mut self, dest: &Path) -> io::Result<()> {
+        if let Some(dir) = dest.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::rename(&self.path, dest).await?;
+        self.path = dest.to_path_buf();
+        Ok(())
+    }
+
+    // Called once the database refers to the file.
+    pub fn keep(mut self) {
+        self.kept = true;
+    }
+}
+
+impl Drop for StagedFile {
+    fn drop(&mut self) {
+        if self.kept {
+            return;
+        }
+        let path = std::mem::take(&mut self.path);
+        tokio::spawn(async move {
+            if let Err(e) = tokio::fs::remove_file(&path).await {
+                if e.kind() != io::ErrorKind::NotFound {
+                    eprintln!("Failed to remove {}: {}", path.display(), e);
+                }
+            }
+        });
+    }
+}
+
+// Reads a staged file's content back, opening it chunk by chunk if it was
+// sealed, so only the plaintext is held. Blocking; use it off the async
+// workers.
+pub struct ContentReader {
+    file: std::fs::File,
+    opener: Option<Opener>,
+    sealed: bool,
+    // Ciphertext read from the file, reused for every chunk
+    chunk: Vec<u8>,
+    buffer: Vec<u8>,
+    pos: usize,
+}
+
+impl ContentReader {
+    pub fn open(path: &Path, opener: Option<Opener>) -> io::Result<Self> {
+        Ok(Self {
+            file: std::fs::File::open(path)?,
+            sealed: opener.is_some(),
+            chunk: if opener.is_some() { vec![0; READ_CHUNK] } else { Vec::new() },
+            opener,
+            buffer: Vec::new(),
+            pos: 0,
+        })
+    }
+}
+
+impl Read for ContentReader {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        // An unsealed file is its own content
+        if !self.sealed {
+            return self.file.read(out);
+        }
+        while self.pos == self.buffer.len() {
+            let n = self.file.read(&mut self.chunk)?;
+            self.pos = 0;
+            self.buffer = match n {
+                0 => match self.opener.take() {
+                    Some(opener) => opener.finish()?,
+                    None => return Ok(0),
+                },
+                n => match self.opener.as_mut() {
+                    Some(opener) => opener.update(&self.chunk[..n])?,
+                    None => return Err(io::Error::new(io::ErrorKind::InvalidData, "data after the last segment")),
+                },
+            };
+        }
+        let len = out.len().min(self.buffer.len() - self.pos);
+        out[..len].copy_from_slice(&self.buffer[self.pos..self.pos + len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+// Streams an upload into a temporary file in `dir`, hashing it on the way
+// and giving up as soon as it passes `max_size` bytes. With a sealer only
+// ciphertext reaches the disk.
+pub async fn stage<S, B, E>(
+    stream: S,
+    dir: &str,
+    max_size: u64,
+    mut sealer: Option<Sealer>,
+) -> Result<StagedFile, UploadError>
+where
+    S: Stream<Item = Result<B, E>>,
+    B: Buf,
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    tokio::fs::create_dir_all(dir).await?;
+
+    let path = Path::new(dir).join(format!(".{}.part", Uuid::new_v4()));
+    let mut file = tokio::fs::OpenOptions::new().write(true).create_new(true).open(&path).await?;
+    let mut staged = StagedFile {
+        path,
+        size: 0,
+        hash: String::new(),
+        stored_len: 0,
+        stored_hash: String::new(),
+        kept: false,
+    };
+
+    let mut hasher = Sha256::new();
+    let mut stored_hasher = Sha256::new();
+    let mut stream = std::pin::pin!(stream);
+    while let Some(mut buf) = stream.try_next().await.map_err(|e| UploadError::Stream(e.into()))? {
+        while buf.has_remaining() {
+            let chunk = buf.chunk();
+            staged.size += chunk.len() as u64;
+            if staged.size > max_size {
+                return Err(UploadError::TooLarge { limit: max_size });
+            }
+            hasher.update(chunk);
+            match sealer.as_mut() {
+                Some(sealer) => write(&mut file, &mut stored_hasher, &mut staged.stored_len, &sealer.update(chunk)).await?,
+                None => write(&mut file, &mut stored_hasher, &mut staged.stored_len, chunk).await?,
+            }
+            let len = chunk.len();
+            buf.advance(len);
+        }
+    }
+    if let Some(sealer) = sealer {
+        write(&mut file, &mut stored_hasher, &mut staged.stored_len, &sealer.finish()).await?;
+    }
+    file.sync_all().await?;
+
+    staged.hash = format!("{:x}", hasher.finalize());
+    staged.stored_hash = format!("{:x}", stored_hasher.finalize());
+    Ok(staged)
+}
+
+async fn write(file: &mut tokio::fs::File, hasher: &mut Sha256, len: &mut u64, data: &[u8]) -> io::Result<()> {
+    hasher.update(data);
+    *len += data.len() as u64;
+    file.write_all(data).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::Keyring;
+    use crate::test_db;
+
+    // Stages `data` in odd-sized pieces and reads it back through a small
+    // buffer, so neither side lines up with READ_CHUNK or the segments.
+    async fn round_trip(data: &[u8], key: Option<&DataKey>) -> Vec<u8> {
+        let pieces: Vec<Result<&[u8], io::Error>> = data.chunks(40_009).map(Ok).collect();
+        let dir = std::env::temp_dir().to_string_lossy().into_owned();
+        let staged = stage(futures_util::stream::iter(pieces), &dir, u64::MAX, key.map(DataKey::sealer)).await.unwrap();
+        assert_eq!(staged.size(), data.len() as u64);
+
+        let mut reader = ContentReader::open(staged.path(), key.map(DataKey::opener)).unwrap();
+        let mut out = Vec::new();
+        let mut buf = [0; 1000];
+        loop {
+            match reader.read(&mut buf).unwrap() {
+                0 => break,
+                n => out.extend_from_slice(&buf[..n]),
+            }
+        }
+        out
+    }
+
+    #[tokio::test]
+    async fn reads_back_what_was_staged() {
+        let data: Vec<u8> = (0..3 * READ_CHUNK + 123).map(|i| (i % 251) as u8).collect();
+        assert_eq!(round_trip(&data, None).await, data);
+        assert_eq!(round_trip(b"", None).await, b"");
+
+        let mut config = test_db::config();
+        config.encryption_keys = vec!["test:AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=".to_string()];
+        let key = Keyring::from_config(&config).new_data_key().unwrap();
+        assert_eq!(round_trip(&data, Some(&key)).await, data);
+        assert_eq!(round_trip(b"", Some(&key)).await, b"");
+    }
+}
@@ -0,0 +1,283 @@
+use serde_json::{Map, Value};
+
+use crate::models::AiCritiqueResponse;
+
+// Feedback categories every critique must cover, matching the score fields.
+pub const CATEGORIES: [&str; 5] = ["structure", "keywords", "action_verbs", "quantified_impact", "readability"];
+
+const MAX_SCORE: f64 = 5.0;
+const MAX_SUGGESTIONS: usize = 10;
+
+// A critique that passed validation, with a note for everything that had to
+// be corrected on the way.
+pub struct Validated {
+    pub critique: AiCritiqueResponse,
+    pub issues: Vec<String>,
+}
+
+// Checks a critique from the AI service before it is stored. Problems that
+// can be corrected without inventing content (out-of-range scores, a bare
+// string where a list was expected) are fixed and reported as issues;
+// anything else rejects the critique with the list of reasons.
+pub fn validate(raw: &Value) -> Result<Validated, Vec<String>> {
+    let Some(obj) = raw.as_object() else {
+        return Err(vec!["critique is not a JSON object".to_string()]);
+    };
+
+    let mut issues = Vec::new();
+    let mut errors = Vec::new();
+
+    let mut scores = [0.0f32; 5];
+    for (i, category) in CATEGORIES.iter().enumerate() {
+        let field = format!("{}_score", category);
+        match score(obj.get(&field), &field, &mut issues) {
+            Ok(value) => scores[i] = value,
+            Err(e) => errors.push(e),
+        }
+    }
+
+    // The overall score is derivable, so a bad one is replaced rather than
+    // rejecting otherwise usable category scores.
+    let overall_score = match score(obj.get("overall_score"), "overall_score", &mut issues) {
+        Ok(value) => value,
+        Err(e) => {
+            issues.push(format!("{}; using the mean of the category scores", e));
+            round1(scores.iter().sum::<f32>() / scores.len() as f32)
+        }
+    };
+
+    let detailed_feedback = match obj.get("detailed_feedback").and_then(Value::as_object) {
+        Some(feedback) => {
+            let mut out = Map::new();
+            for category in CATEGORIES {
+                match feedback_text(feedback.get(category)) {
+                    Some(text) => {
+                        out.insert(category.to_string(), Value::String(text));
+                    }
+                    None => errors.push(format!("detailed_feedback.{} is missing or empty", category)),
+                }
+            }
+            Value::Object(out)
+        }
+        None => {
+            errors.push("detailed_feedback is missing or not an object".to_string());
+            Value::Null
+        }
+    };
+
+    let improvement_suggestions = match obj.get("improvement_suggestions").and_then(Value::as_object) {
+        Some(suggestions) => {
+            let mut out = Map::new();
+            for category in CATEGORIES {
+                let list = suggestion_list(suggestions.get(category), category, &mut issues);
+                out.insert(category.to_string(), Value::from(list));
+            }
+            Value::Object(out)
+        }
+        None => {
+            errors.push("improvement_suggestions is missing or not an object".to_string());
+            Value::Null
+        }
+    };
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let [structure_score, keywords_score, action_verbs_score, quantified_impact_score, readability_score] = scores;
+    Ok(Validated {
+        critique: AiCritiqueResponse {
+            overall_score,
+            structure_score,
+            keywords_score,
+            action_verbs_score,
+            quantified_impact_score,
+            readability_score,
+            detailed_feedback,
+            improvement_suggestions,
+        },
+        issues,
+    })
+}
+
+// Scores are stored as REAL on a 0-5 scale, so they are clamped to it and
+// rounded to one decimal place. Numeric strings are accepted.
+fn score(value: Option<&Value>, field: &str, issues: &mut Vec<String>) -> Result<f32, String> {
+    let number = match value {
+        Some(Value::Number(n)) => n.as_f64(),
+        Some(Value::String(s)) => s.trim().parse::<f64>().ok(),
+        _ => None,
+    };
+
+    let number = match number {
+        Some(n) if n.is_finite() => n,
+        Some(_) => return Err(format!("{} is not a finite number", field)),
+        None => return Err(format!("{} is missing or not a number", field)),
+    };
+
+    let clamped = number.clamp(0.0, MAX_SCORE);
+    if clamped != number {
+        issues.push(format!("{} of {} was clamped to {}", field, number, clamped));
+    }
+
+    Ok(round1(clamped as f32))
+}
+
+fn feedback_text(value: Option<&Value>) -> Option<String> {
+    let text = match value? {
+        Value::String(s) => s.trim().to_string(),
+        // Some responses split feedback into sentences.
+        Value::Array(items) => items
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join(" "),
+        _ => return None,
+    };
+
+    (!text.is_empty()).then_some(text)
+}
+
+fn suggestion_list(value: Option<&Value>, category: &str, issues: &mut Vec<String>) -> Vec<String> {
+    let mut list: Vec<String> = match value {
+        Some(Value::Array(items)) => {
+            let list: Vec<String> = items
+                .iter()
+                .filter_map(Value::as_str)
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
+            if list.len() < items.len() {
+                issues.push(format!("dropped {} invalid improvement_suggestions.{} entries", items.len() - list.len(), category));
+            }
+            list
+        }
+        Some(Value::String(s)) if !s.trim().is_empty() => vec![s.trim().to_string()],
+        _ => {
+            issues.push(format!("improvement_suggestions.{} is missing", category));
+            Vec::new()
+        }
+    };
+
+    if list.len() > MAX_SUGGESTIONS {
+        issues.push(format!("truncated improvement_suggestions.{} to {} entries", category, MAX_SUGGESTIONS));
+        list.truncate(MAX_SUGGESTIONS);
+    }
+    list
+}
+
+fn round1(value: f32) -> f32 {
+    (value * 10.0).round() / 10.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn critique() -> Value {
+        let text = |category: &str| format!("{} feedback", category);
+        json!({
+            "overall_score": 3.5,
+            "structure_score": 4,
+            "keywords_score": "3.25",
+            "action_verbs_score": 3.0,
+            "quantified_impact_score": 2.5,
+            "readability_score": 4.0,
+            "detailed_feedback": CATEGORIES.iter().map(|c| (c.to_string(), json!(text(c)))).collect::<Map<_, _>>(),
+            "improvement_suggestions": CATEGORIES.iter().map(|c| (c.to_string(), json!([text(c)]))).collect::<Map<_, _>>(),
+        })
+    }
+
+    fn rejected(raw: &Value) -> Vec<String> {
+        match validate(raw) {
+            Ok(_) => panic!("critique was accepted"),
+            Err(reasons) => reasons,
+        }
+    }
+
+    #[test]
+    fn accepts_a_well_formed_critique() {
+        let validated = validate(&critique()).unwrap();
+        assert!(validated.issues.is_empty(), "{:?}", validated.issues);
+        assert_eq!(validated.critique.structure_score, 4.0);
+        assert_eq!(validated.critique.keywords_score, 3.3);
+        assert_eq!(validated.critique.detailed_feedback["readability"], "readability feedback");
+    }
+
+    #[test]
+    fn clamps_scores_into_range() {
+        let mut raw = critique();
+        raw["structure_score"] = json!(7.5);
+        raw["keywords_score"] = json!(-2);
+        let validated = validate(&raw).unwrap();
+        assert_eq!(validated.critique.structure_score, 5.0);
+        assert_eq!(validated.critique.keywords_score, 0.0);
+        assert_eq!(
+            validated.issues,
+            ["structure_score of 7.5 was clamped to 5", "keywords_score of -2 was clamped to 0"]
+        );
+    }
+
+    #[test]
+    fn rejects_scores_that_are_not_finite_numbers() {
+        let mut raw = critique();
+        raw["structure_score"] = json!("NaN");
+        raw["readability_score"] = json!("high");
+        assert_eq!(
+            rejected(&raw),
+            ["structure_score is not a finite number", "readability_score is missing or not a number"]
+        );
+    }
+
+    #[test]
+    fn replaces_a_bad_overall_score_with_the_mean() {
+        let mut raw = critique();
+        raw["overall_score"] = json!("inf");
+        let validated = validate(&raw).unwrap();
+        assert_eq!(validated.critique.overall_score, 3.4);
+        assert_eq!(validated.issues, ["overall_score is not a finite number; using the mean of the category scores"]);
+    }
+
+    #[test]
+    fn missing_feedback_is_the_recorded_reason() {
+        let mut raw = critique();
+        raw["detailed_feedback"].as_object_mut().unwrap().remove("keywords");
+        raw["detailed_feedback"]["structure"] = json!("  ");
+        raw["improvement_suggestions"] = json!([]);
+        assert_eq!(
+            rejected(&raw),
+            [
+                "detailed_feedback.structure is missing or empty",
+                "detailed_feedback.keywords is missing or empty",
+                "improvement_suggestions is missing or not an object",
+            ]
+        );
+        assert_eq!(rejected(&json!("a critique")), ["critique is not a JSON object"]);
+    }
+
+    #[test]
+    fn repairs_suggestion_lists() {
+        let mut raw = critique();
+        raw["improvement_suggestions"]["structure"] = json!("Add a summary");
+        raw["improvement_suggestions"]["keywords"] = json!(["Add Rust", 3, ""]);
+        raw["improvement_suggestions"]["readability"] = json!(vec!["Shorten"; 12]);
+        raw["improvement_suggestions"].as_object_mut().unwrap().remove("action_verbs");
+        let validated = validate(&raw).unwrap();
+        let suggestions = &validated.critique.improvement_suggestions;
+        assert_eq!(suggestions["structure"], json!(["Add a summary"]));
+        assert_eq!(suggestions["keywords"], json!(["Add Rust"]));
+        assert_eq!(suggestions["action_verbs"], json!([]));
+        assert_eq!(suggestions["readability"].as_array().unwrap().len(), MAX_SUGGESTIONS);
+        assert_eq!(
+            validated.issues,
+            [
+                "dropped 2 invalid improvement_suggestions.keywords entries",
+                "improvement_suggestions.action_verbs is missing",
+                "truncated improvement_suggestions.readability to 10 entries",
+            ]
+        );
+    }
+}